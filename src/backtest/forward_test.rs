@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use crate::backtest::metrics::BacktestMetrics;
+use crate::backtest::runner::{
+    BacktestConfig, BacktestRunner, EnhancedMarketEvent, HistoricalMarketGenerator,
+};
+use crate::strategies::iron_condor::{IronCondorConfig, IronCondorSignalGenerator};
+
+/// Result of optimizing on in-sample data and re-running the winner out-of-sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardTestReport {
+    /// Candidate with the best in-sample Sharpe ratio
+    pub best_config: IronCondorConfig,
+    /// Metrics of the best candidate on the in-sample data
+    pub in_sample: BacktestMetrics,
+    /// Metrics of the best candidate on the out-of-sample data
+    pub out_of_sample: BacktestMetrics,
+    /// In-sample Sharpe minus out-of-sample Sharpe
+    pub sharpe_gap: f64,
+    /// Sharpe gap as a percentage of the in-sample Sharpe (0 when in-sample Sharpe is 0)
+    pub degradation_pct: f64,
+}
+
+/// Pick the candidate config with the best in-sample Sharpe ratio and measure how much
+/// it degrades on out-of-sample data. A large degradation indicates overfitting.
+#[allow(dead_code)]
+pub async fn forward_test(
+    backtest_config: &BacktestConfig,
+    candidates: &[IronCondorConfig],
+    in_sample: Vec<EnhancedMarketEvent>,
+    out_of_sample: Vec<EnhancedMarketEvent>,
+) -> Option<ForwardTestReport> {
+    let mut best: Option<(IronCondorConfig, BacktestMetrics)> = None;
+
+    for candidate in candidates {
+        let metrics = run_backtest(backtest_config, candidate, in_sample.clone()).await;
+        let is_better = match &best {
+            Some((_, best_metrics)) => metrics.sharpe_ratio > best_metrics.sharpe_ratio,
+            None => true,
+        };
+        if is_better {
+            best = Some((candidate.clone(), metrics));
+        }
+    }
+
+    let (best_config, in_sample_metrics) = best?;
+    let out_of_sample_metrics = run_backtest(backtest_config, &best_config, out_of_sample).await;
+
+    let sharpe_gap = in_sample_metrics.sharpe_ratio - out_of_sample_metrics.sharpe_ratio;
+    let degradation_pct = if in_sample_metrics.sharpe_ratio.abs() > f64::EPSILON {
+        sharpe_gap / in_sample_metrics.sharpe_ratio.abs() * 100.0
+    } else {
+        0.0
+    };
+
+    Some(ForwardTestReport {
+        best_config,
+        in_sample: in_sample_metrics,
+        out_of_sample: out_of_sample_metrics,
+        sharpe_gap,
+        degradation_pct,
+    })
+}
+
+async fn run_backtest(
+    backtest_config: &BacktestConfig,
+    strategy_config: &IronCondorConfig,
+    events: Vec<EnhancedMarketEvent>,
+) -> BacktestMetrics {
+    let market_generator = HistoricalMarketGenerator::new(strategy_config.symbol.clone(), events);
+    let signal_generator = IronCondorSignalGenerator::new(strategy_config.clone());
+    let mut runner =
+        BacktestRunner::new(backtest_config.clone(), market_generator, signal_generator);
+    runner.run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::iron_condor::ProfitTarget;
+    use crate::test_fixtures::create_test_event;
+    use chrono::{DateTime, Duration, Utc};
+
+    fn create_test_events(start: DateTime<Utc>) -> Vec<EnhancedMarketEvent> {
+        (0..30)
+            .map(|day| {
+                let price = 400.0 + (day % 3) as f64;
+                create_test_event(price, start + Duration::days(day))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_forward_test_identical_samples_have_zero_degradation() {
+        let start = Utc::now();
+        let events = create_test_events(start);
+
+        let candidates = vec![
+            IronCondorConfig {
+                profit_target: ProfitTarget::PctOfMax(0.50),
+                ..Default::default()
+            },
+            IronCondorConfig {
+                profit_target: ProfitTarget::PctOfMax(0.25),
+                ..Default::default()
+            },
+        ];
+
+        let report = forward_test(
+            &BacktestConfig::default(),
+            &candidates,
+            events.clone(),
+            events,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            report.in_sample.sharpe_ratio,
+            report.out_of_sample.sharpe_ratio
+        );
+        assert_eq!(report.sharpe_gap, 0.0);
+        assert_eq!(report.degradation_pct, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_forward_test_without_candidates() {
+        let events = create_test_events(Utc::now());
+        let report = forward_test(&BacktestConfig::default(), &[], events.clone(), events).await;
+        assert!(report.is_none());
+    }
+}
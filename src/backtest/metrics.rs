@@ -1,4 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::backtest::runner::CommissionModel;
+use crate::models::iron_condor_position::LegFill;
+use crate::models::options_data::OptionsChain;
+use crate::strategies::iron_condor::ExitReason;
+
+/// Return periods per year for daily sampling, the default Sharpe/Sortino annualization
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
 
 /// Backtesting results and performance metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,7 +26,7 @@ pub struct BacktestMetrics {
     pub sharpe_ratio: f64,
     /// Sortino ratio
     pub sortino_ratio: f64,
-    /// Total number of trades
+    /// Total number of closed trades
     pub total_trades: usize,
     /// Number of winning trades
     pub winning_trades: usize,
@@ -33,6 +42,27 @@ pub struct BacktestMetrics {
     pub profit_factor: f64,
     /// Average holding period in days
     pub avg_holding_days: f64,
+    /// Total return percentage if every position had been held to expiration
+    pub hold_to_expiry_return_pct: Option<f64>,
+    /// Number of entry signals whose limit order did not fill
+    pub unfilled_entries: usize,
+    /// Number of events where equity fell below the margin held and every position was liquidated
+    pub margin_calls: usize,
+    /// Average entry IV minus realized vol over the holding period (the edge of selling premium)
+    pub vol_risk_premium: Option<f64>,
+    /// Extrinsic value that decayed in our favor across closed trades
+    pub total_theta_collected: f64,
+    /// Intrinsic value won or lost across closed trades as the underlying moved. Together
+    /// with `total_theta_collected` it makes up their P&L before commissions.
+    pub total_price_pnl: f64,
+    /// Average maximum adverse excursion (worst unrealized P&L) per trade
+    pub avg_mae: f64,
+    /// Average maximum favorable excursion (best unrealized P&L) per trade
+    pub avg_mfe: f64,
+    /// Premium given up by filling at the recorded prices instead of the quote mids
+    pub total_slippage_cost: f64,
+    /// Return periods per year used to annualize Sharpe and Sortino (252 for daily, 52 for weekly)
+    pub periods_per_year: f64,
 }
 
 impl BacktestMetrics {
@@ -53,7 +83,210 @@ impl BacktestMetrics {
             avg_loss_per_loss: 0.0,
             profit_factor: 0.0,
             avg_holding_days: 0.0,
+            hold_to_expiry_return_pct: None,
+            unfilled_entries: 0,
+            margin_calls: 0,
+            vol_risk_premium: None,
+            total_theta_collected: 0.0,
+            total_price_pnl: 0.0,
+            avg_mae: 0.0,
+            avg_mfe: 0.0,
+            total_slippage_cost: 0.0,
+            periods_per_year: TRADING_DAYS_PER_YEAR,
+        }
+    }
+
+    /// Replay stored trades against a different commission/slippage model.
+    /// Each leg fill is worsened by `slippage_adj` as a fraction of its fill price
+    /// (sells fill lower, buys fill higher) before commissions are charged.
+    #[allow(dead_code)]
+    pub fn recompute_with_costs(
+        trades: &[Trade],
+        commission: &CommissionModel,
+        slippage_adj: f64,
+        initial_capital: f64,
+        days_in_backtest: f64,
+    ) -> BacktestMetrics {
+        let adjusted_cash_flow = |fills: &[LegFill]| -> f64 {
+            fills
+                .iter()
+                .map(|fill| {
+                    let price = if fill.is_sell {
+                        fill.price * (1.0 - slippage_adj)
+                    } else {
+                        fill.price * (1.0 + slippage_adj)
+                    };
+                    LegFill {
+                        price,
+                        ..fill.clone()
+                    }
+                    .cash_flow()
+                })
+                .sum()
+        };
+
+        let mut final_capital = initial_capital;
+        let mut adjusted_trades = Vec::with_capacity(trades.len());
+
+        for trade in trades {
+            let entry_premium = adjusted_cash_flow(&trade.entry_fills);
+            let exit_premium = -adjusted_cash_flow(&trade.exit_fills);
+            let commissions =
+                commission.for_fills(&trade.entry_fills) + commission.for_fills(&trade.exit_fills);
+
+            final_capital += entry_premium - exit_premium - commissions;
+
+            adjusted_trades.push(Trade {
+                entry_price: entry_premium,
+                exit_price: exit_premium,
+                ..trade.clone()
+            });
+        }
+
+        let mut metrics = BacktestMetrics::new(initial_capital);
+        metrics.calculate(final_capital, &adjusted_trades, days_in_backtest);
+        metrics
+    }
+
+    /// Replay stored trades at a fractional `quantity` of contracts each, for sizing
+    /// studies that explore non-integer sizes. Premiums, commissions and excursions are
+    /// scaled per contract; each replayed trade reports `quantity` rounded to whole
+    /// contracts.
+    #[allow(dead_code)]
+    pub fn at_fractional_quantity(
+        trades: &[Trade],
+        commission: &CommissionModel,
+        quantity: f64,
+        initial_capital: f64,
+        days_in_backtest: f64,
+    ) -> BacktestMetrics {
+        let mut final_capital = initial_capital;
+        let mut scaled_trades = Vec::with_capacity(trades.len());
+
+        for trade in trades {
+            let per_contract = quantity / trade.quantity.max(1) as f64;
+            let commissions =
+                commission.for_fills(&trade.entry_fills) + commission.for_fills(&trade.exit_fills);
+            final_capital += (trade.entry_price - trade.exit_price - commissions) * per_contract;
+
+            scaled_trades.push(Trade {
+                entry_price: trade.entry_price * per_contract,
+                exit_price: trade.exit_price * per_contract,
+                quantity: quantity.round() as u32,
+                mae: trade.mae * per_contract,
+                mfe: trade.mfe * per_contract,
+                quantity_f64: Some(quantity),
+                ..trade.clone()
+            });
+        }
+
+        let mut metrics = BacktestMetrics::new(initial_capital);
+        metrics.calculate(final_capital, &scaled_trades, days_in_backtest);
+        metrics
+    }
+
+    /// Winners, losers and P&L of the closed trades grouped by why they were closed, to
+    /// see how much comes from profit targets vs. stops vs. expirations
+    #[allow(dead_code)]
+    pub fn by_exit_reason(trades: &[Trade]) -> HashMap<ExitReason, BucketStats> {
+        let mut buckets: HashMap<ExitReason, BucketStats> = HashMap::new();
+        for trade in trades {
+            let Some(reason) = trade.exit_reason else {
+                continue;
+            };
+            // Credit trades profit by closing for less than they were opened for
+            let pnl = (trade.entry_price - trade.exit_price) * 100.0;
+            let bucket = buckets.entry(reason).or_default();
+            bucket.trades += 1;
+            if pnl > 0.0 {
+                bucket.wins += 1;
+            } else {
+                bucket.losses += 1;
+            }
+            bucket.total_pnl += pnl;
+        }
+
+        for bucket in buckets.values_mut() {
+            bucket.avg_pnl = bucket.total_pnl / bucket.trades as f64;
+        }
+        buckets
+    }
+
+    /// Two-sided exact binomial p-value of the win rate against a coin flip. Small values
+    /// mean the edge is unlikely to be luck. None without trades.
+    pub fn win_rate_p_value(&self) -> Option<f64> {
+        let n = self.total_trades;
+        if n == 0 {
+            return None;
         }
+
+        // P(X <= k) for X ~ Bin(n, 0.5), summed in log space so large samples don't underflow
+        let k = self.winning_trades.min(n - self.winning_trades);
+        let ln_half_n = n as f64 * 0.5f64.ln();
+        let mut ln_choose = 0.0;
+        let mut tail = 0.0;
+        for i in 0..=k {
+            tail += (ln_choose + ln_half_n).exp();
+            ln_choose += ((n - i) as f64).ln() - ((i + 1) as f64).ln();
+        }
+        Some((2.0 * tail).min(1.0))
+    }
+
+    /// Whether the win rate differs from a coin flip at significance level `alpha`
+    pub fn is_statistically_significant(&self, alpha: f64) -> bool {
+        self.win_rate_p_value()
+            .is_some_and(|p_value| p_value < alpha)
+    }
+
+    /// Markdown table of every metric, for sharing results
+    #[allow(dead_code)]
+    pub fn to_markdown(&self) -> String {
+        let money = |value: f64| format!("${:.2}", value);
+        let pct = |value: f64| format!("{:.2}%", value);
+        let ratio = |value: f64| format!("{:.2}", value);
+
+        let mut rows = vec![
+            ("Initial Capital", money(self.initial_capital)),
+            ("Final Capital", money(self.final_capital)),
+            ("Total Return", pct(self.total_return_pct)),
+            ("Annualized Return", pct(self.annualized_return_pct)),
+            ("Max Drawdown", pct(self.max_drawdown_pct)),
+            ("Sharpe Ratio", ratio(self.sharpe_ratio)),
+            ("Sortino Ratio", ratio(self.sortino_ratio)),
+            ("Total Trades", self.total_trades.to_string()),
+            ("Winning Trades", self.winning_trades.to_string()),
+            ("Losing Trades", self.losing_trades.to_string()),
+            ("Win Rate", pct(self.win_rate_pct)),
+            ("Avg Profit per Win", money(self.avg_profit_per_win)),
+            ("Avg Loss per Loss", money(self.avg_loss_per_loss)),
+            ("Profit Factor", ratio(self.profit_factor)),
+            (
+                "Avg Holding Period",
+                format!("{:.2} days", self.avg_holding_days),
+            ),
+            ("Avg MAE", money(self.avg_mae)),
+            ("Avg MFE", money(self.avg_mfe)),
+            ("Theta Collected", money(self.total_theta_collected)),
+            ("Price P&L", money(self.total_price_pnl)),
+            ("Slippage Cost", money(self.total_slippage_cost)),
+            ("Unfilled Entries", self.unfilled_entries.to_string()),
+            ("Margin Calls", self.margin_calls.to_string()),
+        ];
+        if let Some(hold_return) = self.hold_to_expiry_return_pct {
+            rows.push(("Hold-to-Expiry Return", pct(hold_return)));
+        }
+        if let Some(premium) = self.vol_risk_premium {
+            rows.push((
+                "Vol Risk Premium",
+                format!("{:.2} vol points", premium * 100.0),
+            ));
+        }
+
+        let mut markdown = String::from("| Metric | Value |\n|---|---:|\n");
+        for (label, value) in rows {
+            markdown.push_str(&format!("| {} | {} |\n", label, value));
+        }
+        markdown
     }
 
     /// Calculate metrics from backtest results
@@ -66,7 +299,12 @@ impl BacktestMetrics {
         self.annualized_return_pct =
             ((final_capital / self.initial_capital).powf(365.0 / days_in_backtest) - 1.0) * 100.0;
 
-        self.total_trades = trades.len();
+        // Positions still open at the end have no exit to score yet
+        let closed_trades: Vec<&Trade> = trades
+            .iter()
+            .filter(|trade| !trade.exit_fills.is_empty())
+            .collect();
+        self.total_trades = closed_trades.len();
 
         let mut total_profit = 0.0;
         let mut total_loss = 0.0;
@@ -74,10 +312,14 @@ impl BacktestMetrics {
         let mut max_equity = self.initial_capital;
         let mut max_drawdown = 0.0;
         let mut daily_returns = Vec::new();
+        let mut total_mae = 0.0;
+        let mut total_mfe = 0.0;
+        let mut vol_premiums = Vec::new();
 
-        for trade in trades {
-            let pnl = trade.exit_price - trade.entry_price;
-            let profit = pnl * trade.quantity as f64 * 100.0;
+        for trade in closed_trades {
+            // Credit trades profit by closing for less than they were opened for. Both
+            // prices already cover every contract of the trade.
+            let profit = (trade.entry_price - trade.exit_price) * 100.0;
 
             if profit > 0.0 {
                 self.winning_trades += 1;
@@ -102,6 +344,27 @@ impl BacktestMetrics {
 
             let daily_return = profit / self.initial_capital / holding_days;
             daily_returns.push(daily_return);
+
+            if let Some(theta) = trade.theta_collected() {
+                self.total_theta_collected += theta * 100.0;
+            }
+            if let Some(price_pnl) = trade.price_pnl() {
+                self.total_price_pnl += price_pnl * 100.0;
+            }
+
+            self.total_slippage_cost += trade.slippage_cost() * 100.0;
+
+            total_mae += trade.mae * 100.0;
+            total_mfe += trade.mfe * 100.0;
+
+            if let (Some(entry_iv), Some(realized_vol)) = (trade.entry_iv, trade.realized_vol) {
+                vol_premiums.push(entry_iv - realized_vol);
+            }
+        }
+
+        if !vol_premiums.is_empty() {
+            self.vol_risk_premium =
+                Some(vol_premiums.iter().sum::<f64>() / vol_premiums.len() as f64);
         }
 
         self.win_rate_pct = if self.total_trades > 0 {
@@ -136,6 +399,11 @@ impl BacktestMetrics {
             0.0
         };
 
+        if self.total_trades > 0 {
+            self.avg_mae = total_mae / self.total_trades as f64;
+            self.avg_mfe = total_mfe / self.total_trades as f64;
+        }
+
         self.max_drawdown_pct = max_drawdown;
 
         if !daily_returns.is_empty() {
@@ -167,13 +435,13 @@ impl BacktestMetrics {
             let risk_free_rate = 0.0;
 
             self.sharpe_ratio = if std_dev > 0.0 {
-                (mean_return - risk_free_rate) / std_dev * (252.0_f64).sqrt()
+                (mean_return - risk_free_rate) / std_dev * self.periods_per_year.sqrt()
             } else {
                 0.0
             };
 
             self.sortino_ratio = if downside_deviation > 0.0 {
-                (mean_return - risk_free_rate) / downside_deviation * (252.0_f64).sqrt()
+                (mean_return - risk_free_rate) / downside_deviation * self.periods_per_year.sqrt()
             } else if mean_return > risk_free_rate {
                 f64::INFINITY
             } else {
@@ -190,9 +458,9 @@ pub struct Trade {
     pub id: uuid::Uuid,
     /// Symbol
     pub symbol: String,
-    /// Entry price
+    /// Net credit collected at entry, per share and summed over all contracts
     pub entry_price: f64,
-    /// Exit price
+    /// Net debit paid to close, per share and summed over all contracts
     pub exit_price: f64,
     /// Quantity
     pub quantity: u32,
@@ -204,4 +472,536 @@ pub struct Trade {
     pub trade_type: String,
     /// Additional metadata
     pub metadata: serde_json::Value,
+    /// Raw per-leg fills at entry
+    #[serde(default)]
+    pub entry_fills: Vec<LegFill>,
+    /// Raw per-leg fills at exit (empty while the trade is open)
+    #[serde(default)]
+    pub exit_fills: Vec<LegFill>,
+    /// Worst unrealized P&L seen while the position was open
+    #[serde(default)]
+    pub mae: f64,
+    /// Best unrealized P&L seen while the position was open
+    #[serde(default)]
+    pub mfe: f64,
+    /// Larger absolute delta of the two short legs at entry
+    #[serde(default)]
+    pub entry_short_delta: Option<f64>,
+    /// ATM implied volatility of the traded chain at entry
+    #[serde(default)]
+    pub entry_iv: Option<f64>,
+    /// Annualized realized volatility of the underlying while the position was open
+    #[serde(default)]
+    pub realized_vol: Option<f64>,
+    /// Fractional contract count of a sizing simulation, which `quantity` holds rounded
+    #[serde(default)]
+    pub quantity_f64: Option<f64>,
+    /// Traded chain as quoted when the position was entered (only when recording chains)
+    #[serde(default)]
+    pub entry_chain: Option<OptionsChain>,
+    /// Traded chain as quoted when the position was closed (only when recording chains)
+    #[serde(default)]
+    pub exit_chain: Option<OptionsChain>,
+    /// Why the position was closed (None while the trade is open)
+    #[serde(default)]
+    pub exit_reason: Option<ExitReason>,
+}
+
+/// Aggregate results of a group of closed trades, P&L in dollars
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BucketStats {
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub total_pnl: f64,
+    pub avg_pnl: f64,
+}
+
+/// Closed trades whose entry short delta fell in `[lower, upper)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub trades: usize,
+    pub wins: usize,
+    pub win_rate_pct: f64,
+}
+
+/// Bucket closed trades by entry short delta (`bucket_width` wide, e.g. 0.05) and report
+/// the win rate of each bucket, to check whether e.g. 16-delta entries really win ~84% of
+/// the time. Buckets are in ascending delta order; empty ones are left out.
+#[allow(dead_code)]
+pub fn win_rate_by_entry_delta(trades: &[Trade], bucket_width: f64) -> Vec<DeltaBucket> {
+    let mut buckets: BTreeMap<i64, (usize, usize)> = BTreeMap::new();
+
+    for trade in trades.iter().filter(|trade| !trade.exit_fills.is_empty()) {
+        let Some(delta) = trade.entry_short_delta else {
+            continue;
+        };
+        let index = (delta.abs() / bucket_width).floor() as i64;
+        let (count, wins) = buckets.entry(index).or_default();
+        *count += 1;
+        // Credit trades win when they close for less than they were opened for
+        if trade.exit_price < trade.entry_price {
+            *wins += 1;
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(index, (trades, wins))| DeltaBucket {
+            lower: index as f64 * bucket_width,
+            upper: (index + 1) as f64 * bucket_width,
+            trades,
+            wins,
+            win_rate_pct: wins as f64 / trades as f64 * 100.0,
+        })
+        .collect()
+}
+
+impl Trade {
+    /// Per-share net credit the entry would have collected filling every leg at its mid
+    #[allow(dead_code)]
+    pub fn ideal_entry_premium(&self) -> f64 {
+        self.entry_fills.iter().map(LegFill::mid_cash_flow).sum()
+    }
+
+    /// Per-share premium lost to fills away from the mid, entry and exit combined
+    pub fn slippage_cost(&self) -> f64 {
+        self.entry_fills
+            .iter()
+            .chain(&self.exit_fills)
+            .map(LegFill::slippage)
+            .sum()
+    }
+
+    /// Per-share extrinsic value collected: extrinsic credit sold at entry minus extrinsic
+    /// paid back at exit. None while the trade is open or without recorded underlying prices.
+    pub fn theta_collected(&self) -> Option<f64> {
+        if self.exit_fills.is_empty() {
+            return None;
+        }
+        let entry_price = self.metadata.get("underlying_price")?.as_f64()?;
+        let exit_price = self.metadata.get("exit_underlying_price")?.as_f64()?;
+
+        Some(
+            extrinsic_cash_flow(&self.entry_fills, entry_price)
+                + extrinsic_cash_flow(&self.exit_fills, exit_price),
+        )
+    }
+
+    /// Per-share intrinsic value sold at entry minus intrinsic value paid back at exit:
+    /// the part of the P&L that came from the underlying moving, `theta_collected` being
+    /// the rest. None while the trade is open or without recorded underlying prices.
+    pub fn price_pnl(&self) -> Option<f64> {
+        if self.exit_fills.is_empty() {
+            return None;
+        }
+        let entry_price = self.metadata.get("underlying_price")?.as_f64()?;
+        let exit_price = self.metadata.get("exit_underlying_price")?.as_f64()?;
+
+        Some(
+            intrinsic_cash_flow(&self.entry_fills, entry_price)
+                + intrinsic_cash_flow(&self.exit_fills, exit_price),
+        )
+    }
+}
+
+/// Time-value part of the fills' cash flow (positive when selling)
+fn extrinsic_cash_flow(fills: &[LegFill], spot: f64) -> f64 {
+    fills
+        .iter()
+        .map(|fill| {
+            let intrinsic = fill.option_type.intrinsic_value(fill.strike, spot);
+            LegFill {
+                price: fill.price - intrinsic,
+                ..fill.clone()
+            }
+            .cash_flow()
+        })
+        .sum()
+}
+
+/// Intrinsic-value part of the fills' cash flow (positive when selling)
+fn intrinsic_cash_flow(fills: &[LegFill], spot: f64) -> f64 {
+    fills
+        .iter()
+        .map(|fill| {
+            let intrinsic = fill.option_type.intrinsic_value(fill.strike, spot);
+            LegFill {
+                price: intrinsic,
+                ..fill.clone()
+            }
+            .cash_flow()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::options_data::OptionType;
+    use chrono::{Duration, Utc};
+
+    fn fill(option_type: OptionType, strike: f64, is_sell: bool, price: f64) -> LegFill {
+        LegFill {
+            option_type,
+            strike,
+            is_sell,
+            quantity: 1,
+            bid: price - 0.05,
+            ask: price + 0.05,
+            price,
+        }
+    }
+
+    fn create_test_trade() -> Trade {
+        let entry_time = Utc::now();
+        let entry_fills = vec![
+            fill(OptionType::Call, 410.0, true, 1.50),
+            fill(OptionType::Call, 415.0, false, 0.60),
+            fill(OptionType::Put, 390.0, true, 1.40),
+            fill(OptionType::Put, 385.0, false, 0.50),
+        ];
+        let exit_fills = vec![
+            fill(OptionType::Call, 410.0, false, 0.40),
+            fill(OptionType::Call, 415.0, true, 0.10),
+            fill(OptionType::Put, 390.0, false, 0.30),
+            fill(OptionType::Put, 385.0, true, 0.05),
+        ];
+
+        Trade {
+            id: uuid::Uuid::new_v4(),
+            symbol: "SPY".to_string(),
+            entry_price: 1.80,
+            exit_price: 0.55,
+            quantity: 1,
+            entry_time,
+            exit_time: entry_time + Duration::days(1),
+            trade_type: "IronCondor".to_string(),
+            metadata: serde_json::json!({}),
+            entry_fills,
+            exit_fills,
+            mae: -0.40,
+            mfe: 1.20,
+            entry_short_delta: Some(0.16),
+            entry_iv: None,
+            realized_vol: None,
+            quantity_f64: None,
+            entry_chain: None,
+            exit_chain: None,
+            exit_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_recompute_with_costs_higher_commission_reduces_capital() {
+        let trades = vec![create_test_trade(), create_test_trade()];
+
+        let cheap = CommissionModel {
+            per_contract: 0.65,
+            ..Default::default()
+        };
+        let expensive = CommissionModel {
+            per_contract: 2.00,
+            ..Default::default()
+        };
+
+        let cheap_metrics =
+            BacktestMetrics::recompute_with_costs(&trades, &cheap, 0.0, 100_000.0, 30.0);
+        let expensive_metrics =
+            BacktestMetrics::recompute_with_costs(&trades, &expensive, 0.0, 100_000.0, 30.0);
+
+        // 2 trades x 8 leg fills x $1.35 extra commission
+        let expected_difference = 2.0 * 8.0 * (2.00 - 0.65);
+        assert!(expensive_metrics.final_capital < cheap_metrics.final_capital);
+        assert!(
+            (cheap_metrics.final_capital - expensive_metrics.final_capital - expected_difference)
+                .abs()
+                < 1e-9
+        );
+
+        // Replaying is deterministic
+        let replayed =
+            BacktestMetrics::recompute_with_costs(&trades, &expensive, 0.0, 100_000.0, 30.0);
+        assert_eq!(replayed.final_capital, expensive_metrics.final_capital);
+    }
+
+    #[test]
+    fn test_recompute_with_costs_slippage_reduces_capital() {
+        let trades = vec![create_test_trade()];
+        let commission = CommissionModel::default();
+
+        let base =
+            BacktestMetrics::recompute_with_costs(&trades, &commission, 0.0, 100_000.0, 30.0);
+        let slipped =
+            BacktestMetrics::recompute_with_costs(&trades, &commission, 0.05, 100_000.0, 30.0);
+
+        assert!(slipped.final_capital < base.final_capital);
+    }
+
+    #[test]
+    fn test_theta_collected_on_expired_winner_equals_credit() {
+        let mut trade = create_test_trade();
+        // Held to expiration inside the short strikes: every leg expires worthless
+        trade.exit_fills = trade
+            .exit_fills
+            .into_iter()
+            .map(|f| fill(f.option_type, f.strike, f.is_sell, 0.0))
+            .collect();
+        trade.exit_price = 0.0;
+        trade.metadata = serde_json::json!({
+            "underlying_price": 400.0,
+            "exit_underlying_price": 401.0,
+        });
+
+        let mut metrics = BacktestMetrics::new(100_000.0);
+        metrics.calculate(100_180.0, &[trade], 1.0);
+
+        // All strikes were out of the money at entry, so the whole credit was extrinsic
+        assert!((metrics.total_theta_collected - 1.80 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_win_rate_by_entry_delta() {
+        let trade = |delta: f64, exit_price: f64| Trade {
+            exit_price,
+            entry_short_delta: Some(delta),
+            ..create_test_trade()
+        };
+        let mut open = trade(0.12, 0.0);
+        open.exit_fills.clear();
+        let trades = vec![
+            // 10-15 delta: 3 of 4 closed for less than the 1.80 credit
+            trade(0.11, 0.55),
+            trade(-0.14, 0.20),
+            trade(0.12, 0.90),
+            trade(0.13, 3.00),
+            // 15-20 delta: 1 of 2
+            trade(0.16, 0.55),
+            trade(-0.18, 2.50),
+            // Open trades and trades without a recorded delta are ignored
+            open,
+            Trade {
+                entry_short_delta: None,
+                ..create_test_trade()
+            },
+        ];
+
+        let buckets = win_rate_by_entry_delta(&trades, 0.05);
+
+        assert_eq!(buckets.len(), 2);
+        assert!((buckets[0].lower - 0.10).abs() < 1e-12);
+        assert_eq!((buckets[0].trades, buckets[0].wins), (4, 3));
+        assert!((buckets[0].win_rate_pct - 75.0).abs() < 1e-9);
+        assert!((buckets[1].upper - 0.20).abs() < 1e-12);
+        assert_eq!((buckets[1].trades, buckets[1].wins), (2, 1));
+        assert!((buckets[1].win_rate_pct - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_total_slippage_cost_sums_per_trade_slippage() {
+        // The test fills are all at the mid
+        let trade = create_test_trade();
+        assert!(trade.slippage_cost().abs() < 1e-9);
+        assert!((trade.ideal_entry_premium() - trade.entry_price).abs() < 1e-9);
+
+        // Selling the short call 0.10 under the mid and buying back the put 0.05 over it
+        let mut worse = create_test_trade();
+        worse.entry_fills[0].price -= 0.10;
+        worse.exit_fills[2].price += 0.05;
+        assert!((worse.slippage_cost() - 0.15).abs() < 1e-9);
+        let trades = vec![trade, worse];
+
+        let mut metrics = BacktestMetrics::new(100_000.0);
+        metrics.calculate(100_000.0, &trades, 30.0);
+
+        let per_trade: f64 = trades.iter().map(|t| t.slippage_cost() * 100.0).sum();
+        assert!((metrics.total_slippage_cost - per_trade).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weekly_returns_annualize_with_sqrt_52() {
+        let trades: Vec<Trade> = [0.50, 1.20, 2.40, 0.90]
+            .iter()
+            .map(|&exit_price| {
+                let mut trade = create_test_trade();
+                trade.exit_price = exit_price;
+                trade.exit_time = trade.entry_time + Duration::days(7);
+                trade
+            })
+            .collect();
+
+        let returns: Vec<f64> = trades
+            .iter()
+            .map(|t| (t.entry_price - t.exit_price) * 100.0 / 100_000.0 / 7.0)
+            .collect();
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let std_dev =
+            (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64).sqrt();
+
+        let mut weekly = BacktestMetrics::new(100_000.0);
+        weekly.periods_per_year = 52.0;
+        weekly.calculate(100_000.0, &trades, 28.0);
+        assert!((weekly.sharpe_ratio - mean / std_dev * 52.0_f64.sqrt()).abs() < 1e-9);
+
+        let mut daily = BacktestMetrics::new(100_000.0);
+        daily.calculate(100_000.0, &trades, 28.0);
+        let scale = (252.0_f64 / 52.0).sqrt();
+        assert!((daily.sharpe_ratio / weekly.sharpe_ratio - scale).abs() < 1e-9);
+        assert!((daily.sortino_ratio / weekly.sortino_ratio - scale).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_to_markdown_lists_metrics() {
+        let mut metrics = BacktestMetrics::new(100_000.0);
+        metrics.calculate(100_180.0, &[create_test_trade()], 30.0);
+        metrics.margin_calls = 2;
+
+        let markdown = metrics.to_markdown();
+
+        assert!(markdown.starts_with("| Metric | Value |\n|---|---:|\n"));
+        assert!(markdown.contains("| Initial Capital | $100000.00 |"));
+        assert!(markdown.contains("| Final Capital | $100180.00 |"));
+        assert!(markdown.contains("| Total Return | 0.18% |"));
+        assert!(markdown.contains("| Total Trades | 1 |"));
+        assert!(markdown.contains("| Margin Calls | 2 |"));
+        assert!(!markdown.contains("Hold-to-Expiry"));
+    }
+
+    #[test]
+    fn test_win_rate_significance() {
+        let metrics = |total_trades, winning_trades| BacktestMetrics {
+            total_trades,
+            winning_trades,
+            ..BacktestMetrics::new(100_000.0)
+        };
+
+        // 90 wins out of 100 is far beyond a coin flip
+        let lopsided = metrics(100, 90);
+        assert!(lopsided.win_rate_p_value().unwrap() < 1e-10);
+        assert!(lopsided.is_statistically_significant(0.05));
+
+        // 6 of 10 happens by chance more often than not: 2 * P(X <= 4) = 0.754
+        let small = metrics(10, 6);
+        assert!((small.win_rate_p_value().unwrap() - 0.75390625).abs() < 1e-12);
+        assert!(!small.is_statistically_significant(0.05));
+
+        assert_eq!(metrics(0, 0).win_rate_p_value(), None);
+        assert_eq!(metrics(10, 5).win_rate_p_value(), Some(1.0));
+    }
+
+    #[test]
+    fn test_fractional_quantity_scales_pnl_linearly() {
+        let trades = vec![create_test_trade(), create_test_trade()];
+        let commission = CommissionModel {
+            per_contract: 0.01,
+            ..Default::default()
+        };
+        let replay = |quantity| {
+            BacktestMetrics::at_fractional_quantity(&trades, &commission, quantity, 100_000.0, 30.0)
+        };
+
+        let one = replay(1.0);
+        let pnl = |metrics: &BacktestMetrics| metrics.final_capital - metrics.initial_capital;
+        assert!(pnl(&one) > 0.0);
+        for quantity in [0.25, 0.5, 2.5] {
+            let fractional = replay(quantity);
+            assert!((pnl(&fractional) - quantity * pnl(&one)).abs() < 1e-9);
+            assert!(
+                (fractional.avg_profit_per_win - quantity * one.avg_profit_per_win).abs() < 1e-9
+            );
+        }
+
+        // At one contract the replay matches the recorded trades
+        let recorded =
+            BacktestMetrics::recompute_with_costs(&trades, &commission, 0.0, 100_000.0, 30.0);
+        assert!((one.final_capital - recorded.final_capital).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_by_exit_reason_aggregates_per_reason() {
+        let trade = |entry_price, exit_price, exit_reason| Trade {
+            entry_price,
+            exit_price,
+            exit_reason,
+            ..create_test_trade()
+        };
+        let trades = vec![
+            trade(1.80, 0.90, Some(ExitReason::ProfitTarget)),
+            trade(1.60, 0.80, Some(ExitReason::ProfitTarget)),
+            trade(1.80, 3.30, Some(ExitReason::StopLoss)),
+            trade(1.50, 0.00, Some(ExitReason::Expiration)),
+            trade(1.50, 2.50, Some(ExitReason::Expiration)),
+            trade(1.80, 0.00, None),
+        ];
+
+        let breakdown = BacktestMetrics::by_exit_reason(&trades);
+        assert_eq!(breakdown.len(), 3);
+
+        let profit_target = &breakdown[&ExitReason::ProfitTarget];
+        assert_eq!((profit_target.trades, profit_target.wins), (2, 2));
+        assert!((profit_target.total_pnl - 170.0).abs() < 1e-9);
+        assert!((profit_target.avg_pnl - 85.0).abs() < 1e-9);
+
+        let stop_loss = &breakdown[&ExitReason::StopLoss];
+        assert_eq!((stop_loss.trades, stop_loss.losses), (1, 1));
+        assert!((stop_loss.total_pnl + 150.0).abs() < 1e-9);
+
+        let expiration = &breakdown[&ExitReason::Expiration];
+        assert_eq!((expiration.wins, expiration.losses), (1, 1));
+        assert!((expiration.total_pnl - 50.0).abs() < 1e-9);
+
+        // The headline win/loss counts agree with the breakdown
+        let closed: Vec<Trade> = trades
+            .into_iter()
+            .filter(|trade| trade.exit_reason.is_some())
+            .collect();
+        let mut metrics = BacktestMetrics::new(100_000.0);
+        metrics.calculate(100_070.0, &closed, 30.0);
+        let wins: usize = breakdown.values().map(|bucket| bucket.wins).sum();
+        let losses: usize = breakdown.values().map(|bucket| bucket.losses).sum();
+        assert_eq!(
+            (metrics.winning_trades, metrics.losing_trades),
+            (wins, losses)
+        );
+    }
+
+    #[test]
+    fn test_theta_and_price_pnl_sum_to_trade_pnl() {
+        // Expired out of the money: all of the P&L is decay
+        let mut winner = create_test_trade();
+        winner.metadata = serde_json::json!({
+            "underlying_price": 400.0,
+            "exit_underlying_price": 401.0,
+        });
+
+        // Rallied through the 410 short call, which is bought back 3 in the money
+        let mut loser = create_test_trade();
+        loser.exit_fills = vec![
+            fill(OptionType::Call, 410.0, false, 3.60),
+            fill(OptionType::Call, 415.0, true, 0.30),
+            fill(OptionType::Put, 390.0, false, 0.05),
+            fill(OptionType::Put, 385.0, true, 0.01),
+        ];
+        loser.exit_price = 3.34;
+        loser.metadata = serde_json::json!({
+            "underlying_price": 400.0,
+            "exit_underlying_price": 413.0,
+        });
+
+        assert!(winner.price_pnl().unwrap().abs() < 1e-9);
+        assert!((loser.price_pnl().unwrap() + 3.0).abs() < 1e-9);
+        assert!((loser.theta_collected().unwrap() - 1.46).abs() < 1e-9);
+
+        let trades = vec![winner, loser];
+        let mut metrics = BacktestMetrics::new(100_000.0);
+        metrics.calculate(99_971.0, &trades, 1.0);
+
+        let total_pnl = metrics.avg_profit_per_win * metrics.winning_trades as f64
+            - metrics.avg_loss_per_loss * metrics.losing_trades as f64;
+        assert!((total_pnl + 29.0).abs() < 1e-9);
+        assert!((metrics.total_price_pnl + 300.0).abs() < 1e-9);
+        assert!((metrics.total_theta_collected + metrics.total_price_pnl - total_pnl).abs() < 1e-9);
+    }
 }
@@ -1,2 +1,3 @@
+pub mod forward_test;
+pub mod metrics;
 pub mod runner;
-pub mod metrics;
\ No newline at end of file
@@ -1,15 +1,24 @@
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, DurationRound, NaiveDate, Utc};
 use futures::stream::Stream;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use tracing::info;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use crate::backtest::metrics::{BacktestMetrics, Trade};
-use crate::models::options_data::OptionsChain;
-use crate::strategies::iron_condor::{IronCondorSignal, IronCondorSignalGenerator};
+use crate::backtest::metrics::{BacktestMetrics, TRADING_DAYS_PER_YEAR, Trade};
+use crate::math;
+use crate::models::iron_condor_position::{IronCondorPosition, Leg, LegFill, Side, net_premium};
+use crate::models::options_data::{OptionType, OptionsChain, OptionsContract};
+use crate::models::product::{ProductConfig, SettlementType};
+use crate::strategies::iron_condor::{ExitReason, IronCondorSignal, IronCondorSignalGenerator};
 
 /// Configuration for a backtest run
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,10 +29,74 @@ pub struct BacktestConfig {
     pub start_date: DateTime<Utc>,
     /// End date for backtest
     pub end_date: DateTime<Utc>,
-    /// Trading commission per contract
-    pub commission_per_contract: f64,
+    /// Trading commission model
+    pub commission: CommissionModel,
     /// Slippage model settings (percentage)
     pub slippage_pct: f64,
+    /// Skip events whose underlying price is estimated instead of quoted. Off by default:
+    /// some providers estimate every price, which would skip the whole dataset.
+    pub skip_estimated_prices: bool,
+    /// Also compute a shadow result where every entered position is held to expiration
+    pub hold_to_expiry_shadow: bool,
+    /// Probability (0-1) that an entry limit order fills
+    pub fill_probability: f64,
+    /// Seed for the random draws (fill simulation), so runs are reproducible
+    pub random_seed: u64,
+    /// Stop after this many events (None = process the whole dataset)
+    pub max_events: Option<usize>,
+    /// Close positions still open when the data ends at the last mark, so they count as
+    /// realized trades instead of being reported as open
+    pub close_open_at_end: bool,
+    /// Widen chain spreads in the final hours before expiration so late fills get worse
+    pub expiry_spread_widening: bool,
+    /// How often the equity curve is sampled
+    pub equity_granularity: EquityGranularity,
+    /// Expirations within this many days of the best DTE match count as similar, and the
+    /// most liquid of them is traded (e.g. a monthly over a thin weekly)
+    pub expiration_liquidity_tolerance_days: u32,
+    /// Return periods per year used to annualize Sharpe and Sortino
+    pub periods_per_year: f64,
+    /// Execute each signal this many events after it was generated, at that event's
+    /// quotes, to model signal-to-execution latency (0 = fill on the signal's event)
+    pub fill_delay_events: usize,
+    /// Where fills land between the mid and the far side of the quote, as a fraction of
+    /// the half-spread (0 = mid, 1 = buy at the ask and sell at the bid)
+    pub fill_mid_offset: f64,
+    /// Secure every position with the full width of both spreads instead of margin, and
+    /// skip entries that need more than the cash that isn't already set aside
+    pub cash_secured: bool,
+    /// Keep a copy of the traded chain on each trade at entry and exit for post-mortems.
+    /// Off by default: every recorded trade carries two full chains.
+    pub record_chains: bool,
+    /// Skip events whose liquidity score is below this (None = trade every event)
+    pub min_liquidity_score: Option<f64>,
+    /// Close every open position once the day's realized plus unrealized P&L reaches
+    /// this, in the same units as the capital (None = no portfolio target)
+    pub daily_profit_target: Option<f64>,
+    /// Spec of the traded product, which decides how expiring positions settle
+    pub product: ProductConfig,
+}
+
+/// Sampling interval of the equity curve. Coarser intervals keep the last value seen in
+/// each bucket, keyed by the bucket's start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub enum EquityGranularity {
+    EveryEvent,
+    Hourly,
+    Daily,
+}
+
+impl EquityGranularity {
+    /// Equity curve key for an event at `timestamp`
+    fn bucket(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let interval = match self {
+            EquityGranularity::EveryEvent => return timestamp,
+            EquityGranularity::Hourly => Duration::hours(1),
+            EquityGranularity::Daily => Duration::days(1),
+        };
+        timestamp.duration_trunc(interval).unwrap_or(timestamp)
+    }
 }
 
 impl Default for BacktestConfig {
@@ -32,12 +105,84 @@ impl Default for BacktestConfig {
             initial_capital: 100_000.0,
             start_date: Utc::now() - Duration::days(365),
             end_date: Utc::now(),
-            commission_per_contract: 0.65,
+            commission: CommissionModel::default(),
             slippage_pct: 0.05,
+            skip_estimated_prices: false,
+            hold_to_expiry_shadow: false,
+            fill_probability: 1.0,
+            random_seed: 42,
+            max_events: None,
+            close_open_at_end: false,
+            expiry_spread_widening: false,
+            equity_granularity: EquityGranularity::EveryEvent,
+            expiration_liquidity_tolerance_days: 0,
+            periods_per_year: TRADING_DAYS_PER_YEAR,
+            fill_delay_events: 0,
+            fill_mid_offset: 1.0,
+            cash_secured: false,
+            record_chains: false,
+            min_liquidity_score: None,
+            daily_profit_target: None,
+            product: ProductConfig::default(),
+        }
+    }
+}
+
+/// Commission charged on leg fills
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommissionModel {
+    /// Trading commission per contract
+    pub per_contract: f64,
+    /// Fee per short contract assigned at expiration
+    #[serde(default)]
+    pub assignment_fee: f64,
+    /// Fee per long contract exercised at expiration
+    #[serde(default)]
+    pub exercise_fee: f64,
+}
+
+impl Default for CommissionModel {
+    fn default() -> Self {
+        Self {
+            per_contract: 0.65,
+            assignment_fee: 0.0,
+            exercise_fee: 0.0,
         }
     }
 }
 
+impl CommissionModel {
+    /// Total commission for a set of leg fills
+    pub fn for_fills(&self, fills: &[LegFill]) -> f64 {
+        fills
+            .iter()
+            .map(|fill| self.per_contract * fill.quantity as f64)
+            .sum()
+    }
+
+    /// Assignment and exercise fees for settling legs at expiration. `fills` are the
+    /// closing settlement fills: legs that settle in the money were assigned (shorts,
+    /// bought back) or exercised (longs, sold); worthless legs expire without a fee.
+    pub fn for_settlement(&self, fills: &[LegFill]) -> f64 {
+        fills
+            .iter()
+            .filter(|fill| fill.price > 0.0)
+            .map(|fill| {
+                let fee = if fill.is_sell {
+                    self.exercise_fee
+                } else {
+                    self.assignment_fee
+                };
+                fee * fill.quantity as f64
+            })
+            .sum()
+    }
+}
+
+/// Weight of a day's volume relative to open interest in the liquidity score. Volume is
+/// today's activity while open interest accumulates over the contract's life.
+const LIQUIDITY_VOLUME_WEIGHT: f64 = 10.0;
+
 /// Enhanced market data event with options chains
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnhancedMarketEvent {
@@ -45,6 +190,9 @@ pub struct EnhancedMarketEvent {
     pub symbol: String,
     /// Underlying price
     pub underlying_price: f64,
+    /// True if `underlying_price` was estimated rather than quoted
+    #[serde(default)]
+    pub underlying_price_estimated: bool,
     /// Volume
     pub volume: f64,
     /// Implied volatility (VIX-style)
@@ -61,11 +209,107 @@ impl EnhancedMarketEvent {
         self.options_chains.get(expiration_date)
     }
 
+    /// Authoritative underlying price for marking positions, independent of the
+    /// (possibly stale) price stored on each chain
+    pub fn mark_price(&self) -> f64 {
+        self.underlying_price
+    }
+
+    /// Open interest summed over every contract of every chain
+    pub fn total_open_interest(&self) -> u64 {
+        self.contracts()
+            .map(|contract| contract.open_interest as u64)
+            .sum()
+    }
+
+    /// Option volume summed over every contract of every chain
+    pub fn total_volume(&self) -> u64 {
+        self.contracts()
+            .map(|contract| contract.volume as u64)
+            .sum()
+    }
+
+    /// Average per listed contract of open interest plus weighted volume, so a wide chain
+    /// of thin strikes doesn't score as liquid. 0 for an event without contracts.
+    pub fn liquidity_score(&self) -> f64 {
+        let contracts = self.contracts().count();
+        if contracts == 0 {
+            return 0.0;
+        }
+        (self.total_open_interest() as f64 + LIQUIDITY_VOLUME_WEIGHT * self.total_volume() as f64)
+            / contracts as f64
+    }
+
+    fn contracts(&self) -> impl Iterator<Item = &OptionsContract> {
+        self.options_chains
+            .values()
+            .flat_map(|chain| chain.calls.values().chain(chain.puts.values()))
+    }
+
+    /// Smallest DTE across all chains in this event
+    #[allow(dead_code)]
+    pub fn min_dte(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.chain_dtes(now).min()
+    }
+
+    /// Largest DTE across all chains in this event
+    #[allow(dead_code)]
+    pub fn max_dte(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.chain_dtes(now).max()
+    }
+
+    fn chain_dtes(&self, now: DateTime<Utc>) -> impl Iterator<Item = i64> + '_ {
+        self.options_chains
+            .values()
+            .map(move |chain| (chain.expiration - now).num_days())
+    }
+
     /// Get the nearest expiration date
+    #[allow(dead_code)]
     pub fn get_nearest_expiration(&self) -> Option<String> {
         self.options_chains.keys().min_by(|a, b| a.cmp(b)).cloned()
     }
 
+    /// Get the expiration whose DTE is closest to `target_dte`
+    #[allow(dead_code)]
+    pub fn get_expiration_closest_to_dte(&self, target_dte: u32) -> Option<String> {
+        self.options_chains
+            .iter()
+            .min_by_key(|(key, chain)| {
+                let dte = (chain.expiration - self.timestamp).num_days();
+                ((dte - target_dte as i64).abs(), (*key).clone())
+            })
+            .map(|(key, _)| key.clone())
+    }
+
+    /// Expiration to trade: the one closest to `target_dte` (or the nearest one), except
+    /// that among chains within `tolerance_days` of that DTE the most liquid wins
+    pub fn select_expiration(
+        &self,
+        target_dte: Option<u32>,
+        tolerance_days: u32,
+    ) -> Option<String> {
+        let distance = |chain: &OptionsChain| {
+            let dte = (chain.expiration - self.timestamp).num_days();
+            match target_dte {
+                Some(target) => (dte - target as i64).abs(),
+                None => dte,
+            }
+        };
+        let best = self.options_chains.values().map(distance).min()?;
+
+        self.options_chains
+            .iter()
+            .filter(|(_, chain)| distance(chain) <= best + tolerance_days as i64)
+            .max_by(|(a_key, a), (b_key, b)| {
+                a.total_liquidity()
+                    .cmp(&b.total_liquidity())
+                    .then_with(|| distance(b).cmp(&distance(a)))
+                    .then_with(|| b_key.cmp(a_key))
+            })
+            .map(|(key, _)| key.clone())
+    }
+
     /// Update all options chains with new underlying price
     #[allow(dead_code)]
     pub fn update_options_chains(
@@ -116,6 +360,29 @@ impl HistoricalMarketGenerator {
     }
 }
 
+impl HistoricalMarketGenerator {
+    /// Annualized realized volatility of the underlying over each trailing `window` of
+    /// log returns, keyed by the timestamp of the window's last event. Events are taken
+    /// as one per trading day.
+    #[allow(dead_code)]
+    pub fn realized_volatility(&self, window: usize) -> Vec<(DateTime<Utc>, f64)> {
+        let prices: Vec<f64> = self
+            .events
+            .iter()
+            .map(|event| event.underlying_price)
+            .collect();
+
+        prices
+            .windows(window + 1)
+            .zip(self.events.iter().skip(window))
+            .filter_map(|(window_prices, event)| {
+                math::realized_volatility(window_prices, TRADING_DAYS_PER_YEAR)
+                    .map(|vol| (event.timestamp, vol))
+            })
+            .collect()
+    }
+}
+
 impl Stream for HistoricalMarketGenerator {
     type Item = EnhancedMarketEvent;
 
@@ -143,8 +410,41 @@ pub struct BacktestRunner {
     pub trades: Vec<Trade>,
     /// Current capital
     pub current_capital: f64,
-    /// Equity curve (timestamp -> equity)
-    pub equity_curve: HashMap<DateTime<Utc>, f64>,
+    /// Equity curve (timestamp -> equity), ordered by time
+    pub equity_curve: BTreeMap<DateTime<Utc>, EquityPoint>,
+    /// Timestamp of the last processed event
+    pub last_event_time: Option<DateTime<Utc>>,
+    /// Underlying mark price of the last processed event
+    pub last_mark_price: Option<f64>,
+    /// File that every executed signal is appended to as one JSON line
+    pub jsonl_output: Option<PathBuf>,
+}
+
+/// Capital at one point of the equity curve
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EquityPoint {
+    /// Net liquidation value: cash plus what closing the open positions would pay
+    pub gross: f64,
+    /// Gross capital minus margin reserved for open positions
+    pub free: f64,
+}
+
+/// Point-in-time view of an open position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    pub id: Uuid,
+    pub short_call_strike: f64,
+    pub long_call_strike: f64,
+    pub short_put_strike: f64,
+    pub long_put_strike: f64,
+    /// Net credit received at entry
+    pub entry_premium: f64,
+    /// Current cost to close the position at the underlying mark
+    pub mark: f64,
+    /// Unrealized P&L at the underlying mark
+    pub unrealized_pnl: f64,
+    /// Days to expiration as of the last processed event
+    pub dte: i64,
 }
 
 impl BacktestRunner {
@@ -160,10 +460,21 @@ impl BacktestRunner {
             strategy,
             trades: Vec::new(),
             current_capital: config.initial_capital,
-            equity_curve: HashMap::new(),
+            equity_curve: BTreeMap::new(),
+            last_event_time: None,
+            last_mark_price: None,
+            jsonl_output: None,
         }
     }
 
+    /// Append each executed signal to `path` as a JSON line while the run progresses,
+    /// so a dashboard can tail the file
+    #[allow(dead_code)]
+    pub fn with_jsonl_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.jsonl_output = Some(path.into());
+        self
+    }
+
     /// Run the backtest
     pub async fn run(&mut self) -> BacktestMetrics {
         info!(
@@ -174,95 +485,401 @@ impl BacktestRunner {
         // Simple backtest simulation - just process market events
         let mut current_capital = self.config.initial_capital;
         let mut trades = Vec::new();
-        let mut equity_curve = HashMap::new();
+        let mut equity_curve = BTreeMap::new();
 
         // Process all market events
         let mut event_count = 0;
+        let mut estimated_price_events = 0;
+        let mut unfilled_entries = 0;
+        let mut margin_calls = 0;
+        let mut rng = StdRng::seed_from_u64(self.config.random_seed);
         let mut active_trades: HashMap<Uuid, Trade> = HashMap::new();
+        let mut reserved_margin: HashMap<Uuid, f64> = HashMap::new();
+        // Underlying prices seen while each position was open, for its realized vol
+        let mut holding_prices: HashMap<Uuid, Vec<(DateTime<Utc>, f64)>> = HashMap::new();
+        // Signals waiting out the fill delay, with the event count they execute at
+        let mut pending_signals: VecDeque<(usize, IronCondorSignal)> = VecDeque::new();
+        let mut position_expirations: HashMap<Uuid, DateTime<Utc>> = HashMap::new();
+        let mut jsonl_output = self.open_jsonl_output();
+        // The day's P&L is measured from the last net liquidation value of the day before
+        let mut day_start: Option<(NaiveDate, f64)> = None;
+        let mut previous_net_liquidation = self.config.initial_capital;
+        // Quotes of the last processed event, for closing what is still open when the data ends
+        let mut last_event: Option<EnhancedMarketEvent> = None;
+
+        // Shadow book that ignores exit signals and settles every position at expiration
+        let mut shadow_capital = self.config.initial_capital;
+        let mut shadow_positions: Vec<IronCondorPosition> = Vec::new();
 
-        while let Some(event) = self.market_generator.next_event().await {
+        while self
+            .config
+            .max_events
+            .is_none_or(|max_events| event_count < max_events)
+            && let Some(event) = self.market_generator.next_event().await
+        {
             event_count += 1;
 
-            // Update equity curve with current capital (mark-to-market)
-            equity_curve.insert(event.timestamp, current_capital);
-
-            // Use the nearest expiration options chain for signal generation
-            if let Some(expiration_key) = event.get_nearest_expiration() {
-                if let Some(options_chain) = event.get_options_chain(&expiration_key) {
-                    if let Some(signal) = self
-                        .strategy
-                        .generate_signal_with_options_chain(options_chain)
-                    {
-                        match signal {
-                            IronCondorSignal::Enter {
-                                position,
-                                timestamp,
-                            } => {
-                                let trade = Trade {
-                                    id: position.id,
-                                    symbol: event.symbol.clone(),
-                                    entry_price: position.entry_premium, // Use premium as "price"
-                                    exit_price: 0.0,                     // Will be set on exit
-                                    quantity: position.quantity,
-                                    entry_time: timestamp,
-                                    exit_time: timestamp, // Will be updated on exit
-                                    trade_type: "IronCondor".to_string(),
-                                    metadata: serde_json::json!({
-                                        "entry_premium": position.entry_premium,
-                                        "max_profit": position.max_profit(),
-                                        "max_loss": position.max_loss(),
-                                        "short_call_strike": position.short_call.strike,
-                                        "long_call_strike": position.long_call.strike,
-                                        "short_put_strike": position.short_put.strike,
-                                        "long_put_strike": position.long_put.strike,
-                                        "underlying_price": options_chain.underlying_price,
-                                        "status": "open"
-                                    }),
-                                };
-
-                                // Apply premium immediately for credit spreads
-                                let commission = self.config.commission_per_contract
-                                    * position.quantity as f64
-                                    * 4.0; // 4 legs
-                                let net_premium = position.entry_premium - commission;
-                                current_capital += net_premium;
-
-                                active_trades.insert(position.id, trade);
+            // Update equity curve with the book's net liquidation value (mark-to-market)
+            let margin_in_use: f64 = reserved_margin.values().sum();
+            let open_value: f64 = active_trades
+                .keys()
+                .filter_map(|id| self.strategy.state.active_positions.get(id))
+                .map(|position| {
+                    liquidation_value(position, event.options_chains.values(), event.mark_price())
+                })
+                .sum();
+            let net_liquidation = current_capital + open_value;
+            equity_curve.insert(
+                self.config.equity_granularity.bucket(event.timestamp),
+                EquityPoint {
+                    gross: net_liquidation,
+                    free: net_liquidation - margin_in_use,
+                },
+            );
+            let today = event.timestamp.date_naive();
+            let day_start_equity = match day_start {
+                Some((day, equity)) if day == today => equity,
+                _ => {
+                    day_start = Some((today, previous_net_liquidation));
+                    previous_net_liquidation
+                }
+            };
+            previous_net_liquidation = net_liquidation;
+
+            // Strategy decisions on a made-up underlying price are meaningless
+            if event.underlying_price_estimated {
+                estimated_price_events += 1;
+                if self.config.skip_estimated_prices {
+                    warn!(
+                        "Skipping event at {}: underlying price ${:.2} is estimated, not quoted",
+                        event.timestamp, event.underlying_price
+                    );
+                    continue;
+                }
+                warn!(
+                    "Event at {} uses an ESTIMATED underlying price ${:.2}; signals may be unreliable",
+                    event.timestamp, event.underlying_price
+                );
+            }
+
+            if let Some(min_score) = self.config.min_liquidity_score
+                && event.liquidity_score() < min_score
+            {
+                warn!(
+                    "Skipping event at {}: liquidity score {:.1} is below {:.1}",
+                    event.timestamp,
+                    event.liquidity_score(),
+                    min_score
+                );
+                continue;
+            }
+
+            let previous_mark = self.last_event_time.zip(self.last_mark_price);
+            self.last_event_time = Some(event.timestamp);
+            self.last_mark_price = Some(event.mark_price());
+
+            // Track the excursions of every open position at this event's mark
+            for (id, trade) in active_trades.iter_mut() {
+                if let Some(position) = self.strategy.state.active_positions.get(id) {
+                    let unrealized_pnl = position.calculate_pnl(event.mark_price());
+                    trade.mae = trade.mae.min(unrealized_pnl);
+                    trade.mfe = trade.mfe.max(unrealized_pnl);
+                }
+                holding_prices
+                    .entry(*id)
+                    .or_default()
+                    .push((event.timestamp, event.mark_price()));
+            }
+
+            // Expired positions can no longer be traded out of: they settle at intrinsic value
+            let expired_ids: Vec<Uuid> = active_trades
+                .keys()
+                .filter(|id| {
+                    self.strategy
+                        .state
+                        .active_positions
+                        .get(id)
+                        .is_some_and(|position| position.short_call.expiration <= event.timestamp)
+                })
+                .copied()
+                .collect();
+            for id in expired_ids {
+                let settlement_price = match position_expirations.get(&id) {
+                    Some(expiration) => self.settlement_price(*expiration, previous_mark, &event),
+                    None => event.mark_price(),
+                };
+                if let Some((mut trade, cash_paid)) = self.force_close(
+                    &mut active_trades,
+                    &id,
+                    std::iter::empty(),
+                    settlement_price,
+                    event.timestamp,
+                    ExitReason::Expiration,
+                ) {
+                    if let Some(expiration) = position_expirations.get(&id) {
+                        trade.exit_chain = self.recorded_chain(&event, *expiration);
+                    }
+                    current_capital -= cash_paid;
+                    reserved_margin.remove(&id);
+                    trades.push(trade);
+                }
+            }
 
+            // Equity after paying to close every open position must still cover their margin
+            let margin_in_use: f64 = reserved_margin.values().sum();
+            let close_cost: f64 = active_trades
+                .keys()
+                .filter_map(|id| self.strategy.state.active_positions.get(id))
+                .map(|position| {
+                    -liquidation_value(position, event.options_chains.values(), event.mark_price())
+                })
+                .sum();
+            if !active_trades.is_empty() && current_capital - close_cost < margin_in_use {
+                margin_calls += 1;
+                warn!(
+                    "Margin call at {}: equity ${:.2} below ${:.2} margin, liquidating {} positions",
+                    event.timestamp,
+                    current_capital - close_cost,
+                    margin_in_use,
+                    active_trades.len()
+                );
+                let open_ids: Vec<Uuid> = active_trades.keys().copied().collect();
+                for id in open_ids {
+                    if let Some((mut trade, cash_paid)) = self.force_close(
+                        &mut active_trades,
+                        &id,
+                        event.options_chains.values(),
+                        event.mark_price(),
+                        event.timestamp,
+                        ExitReason::MarginCall,
+                    ) {
+                        if let Some(expiration) = position_expirations.get(&id) {
+                            trade.exit_chain = self.recorded_chain(&event, *expiration);
+                        }
+                        current_capital -= cash_paid;
+                        reserved_margin.remove(&id);
+                        trades.push(trade);
+                    }
+                }
+            }
+
+            for (mut trade, cash_paid) in self.close_on_daily_profit_target(
+                &mut active_trades,
+                current_capital,
+                day_start_equity,
+                &event,
+            ) {
+                if let Some(expiration) = position_expirations.get(&trade.id) {
+                    trade.exit_chain = self.recorded_chain(&event, *expiration);
+                }
+                current_capital -= cash_paid;
+                reserved_margin.remove(&trade.id);
+                trades.push(trade);
+            }
+
+            shadow_positions.retain(|position| {
+                if position.short_call.expiration <= event.timestamp {
+                    let settlement_price = self.settlement_price(
+                        position.short_call.expiration,
+                        previous_mark,
+                        &event,
+                    );
+                    let fees =
+                        self.settlement_commission(&position.settlement_fills(settlement_price));
+                    shadow_capital -= position.settlement_cost(settlement_price) + fees;
+                    false
+                } else {
+                    true
+                }
+            });
+
+            // Use the expiration closest to the strategy's target DTE (or the nearest one)
+            let expiration_key = event.select_expiration(
+                self.strategy.config.target_dte,
+                self.config.expiration_liquidity_tolerance_days,
+            );
+            let new_signal = expiration_key
+                .and_then(|key| event.get_options_chain(&key))
+                .map(|chain| self.quoted_chain(chain))
+                .and_then(|chain| {
+                    self.strategy
+                        .generate_signal_with_mark_price(&chain, event.mark_price())
+                })
+                .map(|signal| self.with_fill_offset(signal));
+
+            let due_signals: Vec<IronCondorSignal> = if self.config.fill_delay_events == 0 {
+                new_signal.into_iter().collect()
+            } else {
+                let execute_at = event_count + self.config.fill_delay_events;
+                pending_signals.extend(new_signal.map(|signal| (execute_at, signal)));
+                let mut due = Vec::new();
+                while pending_signals
+                    .front()
+                    .is_some_and(|(execute_at, _)| *execute_at <= event_count)
+                    && let Some((_, signal)) = pending_signals.pop_front()
+                {
+                    due.extend(self.reprice_signal(signal, &event, &position_expirations));
+                }
+                due
+            };
+
+            for signal in due_signals {
+                let signal_line = jsonl_output.is_some().then(
+                    || serde_json::json!({ "timestamp": event.timestamp, "signal": &signal }),
+                );
+                match signal {
+                    IronCondorSignal::Enter {
+                        position,
+                        timestamp,
+                    } => {
+                        if self.config.cash_secured {
+                            let collateral = self.capital_requirement(&position);
+                            let available = current_capital - reserved_margin.values().sum::<f64>();
+                            if collateral > available {
+                                self.strategy.cancel_entry(&position.id);
                                 info!(
-                                    "Iron Condor ENTRY: ID={}, Premium=${:.2}, Net=${:.2} (after commission)",
-                                    position.id, position.entry_premium, net_premium
+                                    "Iron Condor entry {} skipped: needs ${:.2} cash-secured, ${:.2} available",
+                                    position.id, collateral, available
                                 );
+                                continue;
                             }
-                            IronCondorSignal::Exit {
-                                position_id,
-                                exit_premium,
-                                timestamp,
-                                reason,
-                            } => {
-                                // Find and close the corresponding trade
-                                if let Some(mut trade) = active_trades.remove(&position_id) {
-                                    trade.exit_price = exit_premium;
-                                    trade.exit_time = timestamp;
-
-                                    // Calculate P&L (entry premium - exit premium)
-                                    let pnl = trade.entry_price - trade.exit_price;
-                                    let commission = self.config.commission_per_contract
-                                        * trade.quantity as f64
-                                        * 4.0; // 4 legs
-                                    let net_pnl = pnl - commission;
-
-                                    current_capital += net_pnl;
-                                    trades.push(trade);
-
-                                    info!(
-                                        "Iron Condor EXIT: ID={}, Exit Premium=${:.2}, P&L=${:.2}, Reason={}",
-                                        position_id, exit_premium, net_pnl, reason
-                                    );
-                                }
+                        }
+
+                        // The entry limit order only fills when the draw lands below the fill probability
+                        if rng.random::<f64>() >= self.config.fill_probability {
+                            unfilled_entries += 1;
+                            self.strategy.cancel_entry(&position.id);
+                            info!("Iron Condor entry {} not filled", position.id);
+                            continue;
+                        }
+
+                        write_jsonl(&mut jsonl_output, signal_line.as_ref());
+
+                        let trade = self.open_trade(&position, &event, timestamp);
+
+                        // Apply premium immediately for credit spreads
+                        let commission = self.config.commission.for_fills(&trade.entry_fills);
+                        let net_premium = position.entry_premium - commission;
+                        current_capital += net_premium;
+                        reserved_margin.insert(position.id, self.capital_requirement(&position));
+
+                        holding_prices
+                            .insert(position.id, vec![(event.timestamp, event.mark_price())]);
+                        position_expirations.insert(position.id, position.short_call.expiration);
+                        active_trades.insert(position.id, trade);
+
+                        if self.config.hold_to_expiry_shadow {
+                            shadow_capital += net_premium;
+                            shadow_positions.push(*position.clone());
+                        }
+
+                        info!(
+                            "Iron Condor ENTRY: ID={}, Premium=${:.2}, Net=${:.2} (after commission)",
+                            position.id, position.entry_premium, net_premium
+                        );
+                    }
+                    IronCondorSignal::Exit {
+                        position_id,
+                        exit_premium,
+                        fills,
+                        timestamp,
+                        reason,
+                    } => {
+                        // Find and close the corresponding trade
+                        if let Some(mut trade) = active_trades.remove(&position_id) {
+                            write_jsonl(&mut jsonl_output, signal_line.as_ref());
+
+                            trade.exit_price = exit_premium;
+                            trade.exit_time = timestamp;
+                            trade.exit_fills = fills;
+                            trade.exit_reason = Some(reason);
+                            if let Some(expiration) = position_expirations.get(&position_id) {
+                                trade.exit_chain = self.recorded_chain(&event, *expiration);
+                            }
+                            if let Some(metadata) = trade.metadata.as_object_mut() {
+                                metadata.insert(
+                                    "exit_underlying_price".to_string(),
+                                    serde_json::json!(event.mark_price()),
+                                );
+                                metadata.insert("status".to_string(), serde_json::json!("closed"));
+                                metadata.insert(
+                                    "reason".to_string(),
+                                    serde_json::json!(reason.to_string()),
+                                );
                             }
+
+                            // Calculate P&L (entry premium - exit premium)
+                            let pnl = trade.entry_price - trade.exit_price;
+                            let commission = self.config.commission.for_fills(&trade.exit_fills);
+                            let net_pnl = pnl - commission;
+
+                            // The credit was booked at entry, so only the closing debit moves cash
+                            current_capital -= trade.exit_price + commission;
+                            reserved_margin.remove(&position_id);
+                            trades.push(trade);
+
+                            info!(
+                                "Iron Condor EXIT: ID={}, Exit Premium=${:.2}, P&L=${:.2}, Reason={}",
+                                position_id, exit_premium, net_pnl, reason
+                            );
                         }
                     }
+                    IronCondorSignal::Adjust {
+                        position_id,
+                        added_quantity,
+                        added_premium,
+                        fills,
+                        ..
+                    } => {
+                        if let Some(trade) = active_trades.get_mut(&position_id) {
+                            write_jsonl(&mut jsonl_output, signal_line.as_ref());
+
+                            let commission = self.config.commission.for_fills(&fills);
+                            current_capital += added_premium - commission;
+                            trade.quantity += added_quantity;
+                            trade.entry_price += added_premium;
+                            trade.entry_fills.extend(fills);
+                            if let Some(position) =
+                                self.strategy.state.active_positions.get(&position_id)
+                            {
+                                reserved_margin
+                                    .insert(position_id, self.capital_requirement(position));
+                            }
+
+                            info!(
+                                "Iron Condor TOP-UP: ID={}, +{} contracts, Premium=${:.2}",
+                                position_id, added_quantity, added_premium
+                            );
+                        }
+                    }
+                }
+            }
+
+            last_event = Some(event);
+        }
+
+        if !pending_signals.is_empty() {
+            warn!(
+                "{} delayed signals were still waiting to fill when the data ended",
+                pending_signals.len()
+            );
+        }
+
+        if self.config.close_open_at_end
+            && let Some(event) = &last_event
+        {
+            let open_ids: Vec<Uuid> = active_trades.keys().copied().collect();
+            for id in open_ids {
+                if let Some((trade, cash_paid)) = self.force_close(
+                    &mut active_trades,
+                    &id,
+                    event.options_chains.values(),
+                    event.mark_price(),
+                    event.timestamp,
+                    ExitReason::EndOfBacktest,
+                ) {
+                    current_capital -= cash_paid;
+                    trades.push(trade);
                 }
             }
         }
@@ -282,16 +899,53 @@ impl BacktestRunner {
             event_count,
             trades.len()
         );
+        if self.config.skip_estimated_prices
+            && event_count > 0
+            && estimated_price_events == event_count
+        {
+            error!(
+                "All {} events had an estimated underlying price and were skipped; \
+                 disable skip_estimated_prices to trade on them",
+                event_count
+            );
+        } else if estimated_price_events > 0 {
+            warn!(
+                "{} of {} events had an estimated underlying price",
+                estimated_price_events, event_count
+            );
+        }
+
+        for trade in &mut trades {
+            trade.realized_vol = holding_prices
+                .get(&trade.id)
+                .and_then(|prices| holding_realized_vol(prices));
+        }
 
         self.trades = trades;
         self.current_capital = current_capital;
         self.equity_curve = equity_curve;
 
         let mut metrics = BacktestMetrics::new(self.config.initial_capital);
+        metrics.periods_per_year = self.config.periods_per_year;
 
         let days_in_backtest = (self.config.end_date - self.config.start_date).num_days() as f64;
 
         metrics.calculate(self.current_capital, &self.trades, days_in_backtest);
+        metrics.unfilled_entries = unfilled_entries;
+        metrics.margin_calls = margin_calls;
+
+        if self.config.hold_to_expiry_shadow {
+            // Positions expiring after the data ends settle at the last observed price
+            if let Some(mark_price) = self.last_mark_price {
+                for position in &shadow_positions {
+                    shadow_capital -= position.settlement_cost(mark_price);
+                }
+            }
+            metrics.hold_to_expiry_return_pct = Some(
+                (shadow_capital - self.config.initial_capital) / self.config.initial_capital
+                    * 100.0,
+            );
+        }
 
         info!(
             "Backtest completed. Final capital: ${:.2}",
@@ -300,4 +954,1733 @@ impl BacktestRunner {
 
         metrics
     }
+
+    /// Run over only the loaded events within `[start, end]`, starting from a fresh strategy
+    /// state. The full dataset stays loaded, so the same runner can be sliced repeatedly.
+    #[allow(dead_code)]
+    pub async fn run_range(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> BacktestMetrics {
+        self.strategy.reset();
+        self.last_event_time = None;
+        self.last_mark_price = None;
+
+        let all_events = std::mem::take(&mut self.market_generator.events);
+        self.market_generator.events = all_events
+            .iter()
+            .filter(|event| event.timestamp >= start && event.timestamp <= end)
+            .cloned()
+            .collect();
+        self.market_generator.current_idx = 0;
+        let dates = (self.config.start_date, self.config.end_date);
+        (self.config.start_date, self.config.end_date) = (start, end);
+
+        let metrics = self.run().await;
+
+        self.market_generator.events = all_events;
+        self.market_generator.current_idx = 0;
+        (self.config.start_date, self.config.end_date) = dates;
+        metrics
+    }
+
+    /// Re-quote a delayed signal at `event`, the event it executes at. Entries the chain
+    /// can no longer quote as a credit are cancelled; exits and top-ups whose legs are
+    /// missing keep their original fills.
+    fn reprice_signal(
+        &mut self,
+        signal: IronCondorSignal,
+        event: &EnhancedMarketEvent,
+        position_expirations: &HashMap<Uuid, DateTime<Utc>>,
+    ) -> Option<IronCondorSignal> {
+        let mid_offset = self.config.fill_mid_offset;
+        let chain_expiring = |expiration: Option<&DateTime<Utc>>| {
+            let expiration = expiration?;
+            event
+                .options_chains
+                .values()
+                .find(|chain| chain.expiration == *expiration)
+                .map(|chain| self.quoted_chain(chain))
+        };
+
+        match signal {
+            IronCondorSignal::Enter { mut position, .. } => {
+                let chain = chain_expiring(Some(&position.short_call.expiration));
+                let legs = chain.as_ref().and_then(|chain| {
+                    Some((
+                        chain.get_call(position.short_call.strike)?.clone(),
+                        chain.get_call(position.long_call.strike)?.clone(),
+                        chain.get_put(position.short_put.strike)?.clone(),
+                        chain.get_put(position.long_put.strike)?.clone(),
+                        chain.underlying_price,
+                    ))
+                });
+                let Some((short_call, long_call, short_put, long_put, underlying_price)) = legs
+                else {
+                    warn!(
+                        "Delayed entry {} cancelled: its legs are no longer quoted",
+                        position.id
+                    );
+                    self.strategy.cancel_entry(&position.id);
+                    return None;
+                };
+
+                position.short_call = short_call;
+                position.long_call = long_call;
+                position.short_put = short_put;
+                position.long_put = long_put;
+                position.entry_premium = net_premium(&position.legs());
+                position.entry_time = event.timestamp;
+                position.entry_underlying_price = underlying_price;
+                if position.entry_premium <= 0.0 {
+                    info!(
+                        "Delayed entry {} cancelled: net premium ${:.2} is no longer a credit",
+                        position.id, position.entry_premium
+                    );
+                    self.strategy.cancel_entry(&position.id);
+                    return None;
+                }
+
+                self.strategy
+                    .state
+                    .active_positions
+                    .insert(position.id, (*position).clone());
+                Some(IronCondorSignal::Enter {
+                    position,
+                    timestamp: event.timestamp,
+                })
+            }
+            IronCondorSignal::Exit {
+                position_id,
+                exit_premium,
+                fills,
+                reason,
+                ..
+            } => {
+                let chain = chain_expiring(position_expirations.get(&position_id));
+                let (exit_premium, fills) =
+                    match chain.and_then(|chain| requote_fills(&fills, &chain, mid_offset)) {
+                        Some(fills) => (-fills.iter().map(LegFill::cash_flow).sum::<f64>(), fills),
+                        None => (exit_premium, fills),
+                    };
+                Some(IronCondorSignal::Exit {
+                    position_id,
+                    exit_premium,
+                    fills,
+                    timestamp: event.timestamp,
+                    reason,
+                })
+            }
+            IronCondorSignal::Adjust {
+                position_id,
+                added_quantity,
+                added_premium,
+                fills,
+                ..
+            } => {
+                let chain = chain_expiring(position_expirations.get(&position_id));
+                let (repriced_premium, fills) =
+                    match chain.and_then(|chain| requote_fills(&fills, &chain, mid_offset)) {
+                        Some(fills) => (fills.iter().map(LegFill::cash_flow).sum::<f64>(), fills),
+                        None => (added_premium, fills),
+                    };
+                if let Some(position) = self.strategy.state.active_positions.get_mut(&position_id) {
+                    position.entry_premium += repriced_premium - added_premium;
+                }
+                Some(IronCondorSignal::Adjust {
+                    position_id,
+                    added_quantity,
+                    added_premium: repriced_premium,
+                    fills,
+                    timestamp: event.timestamp,
+                })
+            }
+        }
+    }
+
+    /// Trade record for `position` entered at `timestamp` on `event`
+    fn open_trade(
+        &self,
+        position: &IronCondorPosition,
+        event: &EnhancedMarketEvent,
+        timestamp: DateTime<Utc>,
+    ) -> Trade {
+        let unrealized_pnl = position.calculate_pnl(event.mark_price());
+        Trade {
+            id: position.id,
+            symbol: event.symbol.clone(),
+            entry_price: position.entry_premium, // Use premium as "price"
+            exit_price: 0.0,                     // Will be set on exit
+            quantity: position.quantity,
+            entry_time: timestamp,
+            exit_time: timestamp, // Will be updated on exit
+            trade_type: "IronCondor".to_string(),
+            metadata: serde_json::json!({
+                "entry_premium": position.entry_premium,
+                "max_profit": position.max_profit(),
+                "max_loss": position.max_loss(),
+                "short_call_strike": position.short_call.strike,
+                "long_call_strike": position.long_call.strike,
+                "short_put_strike": position.short_put.strike,
+                "long_put_strike": position.long_put.strike,
+                "underlying_price": position.entry_underlying_price,
+                "status": "open"
+            }),
+            entry_fills: position.entry_fills(),
+            exit_fills: Vec::new(),
+            mae: unrealized_pnl,
+            mfe: unrealized_pnl,
+            entry_short_delta: Some(
+                position
+                    .short_call
+                    .greeks
+                    .delta
+                    .abs()
+                    .max(position.short_put.greeks.delta.abs()),
+            ),
+            entry_iv: event
+                .options_chains
+                .values()
+                .find(|chain| chain.expiration == position.short_call.expiration)
+                .and_then(OptionsChain::atm_iv),
+            realized_vol: None,
+            quantity_f64: None,
+            entry_chain: self.recorded_chain(event, position.short_call.expiration),
+            exit_chain: None,
+            exit_reason: None,
+        }
+    }
+
+    /// Close every open trade once the book's realized plus unrealized P&L since
+    /// `day_start_equity` reaches the daily profit target. `cash` is the capital after
+    /// the trades realized so far; returns the closed trades with the cash paid for each.
+    fn close_on_daily_profit_target(
+        &mut self,
+        active_trades: &mut HashMap<Uuid, Trade>,
+        cash: f64,
+        day_start_equity: f64,
+        event: &EnhancedMarketEvent,
+    ) -> Vec<(Trade, f64)> {
+        let Some(target) = self.config.daily_profit_target else {
+            return Vec::new();
+        };
+        let open_value: f64 = active_trades
+            .keys()
+            .filter_map(|id| self.strategy.state.active_positions.get(id))
+            .map(|position| {
+                liquidation_value(position, event.options_chains.values(), event.mark_price())
+            })
+            .sum();
+        let day_pnl = cash + open_value - day_start_equity;
+        if active_trades.is_empty() || day_pnl < target {
+            return Vec::new();
+        }
+
+        info!(
+            "Daily profit target hit at {}: P&L ${:.2} reached ${:.2}, closing {} positions",
+            event.timestamp,
+            day_pnl,
+            target,
+            active_trades.len()
+        );
+        let open_ids: Vec<Uuid> = active_trades.keys().copied().collect();
+        open_ids
+            .iter()
+            .filter_map(|id| {
+                self.force_close(
+                    active_trades,
+                    id,
+                    event.options_chains.values(),
+                    event.mark_price(),
+                    event.timestamp,
+                    ExitReason::PortfolioProfitTarget,
+                )
+            })
+            .collect()
+    }
+
+    /// Close an open trade for an exit the strategy didn't signal, at the quotes of the
+    /// chain in `chains` with its expiration or, without one, at intrinsic value with the
+    /// underlying at `mark_price`. Expiring trades pass no chains so they settle. Returns
+    /// the closed trade and the cash paid to close it, commission included.
+    fn force_close<'a>(
+        &mut self,
+        active_trades: &mut HashMap<Uuid, Trade>,
+        id: &Uuid,
+        chains: impl Iterator<Item = &'a OptionsChain>,
+        mark_price: f64,
+        timestamp: DateTime<Utc>,
+        reason: ExitReason,
+    ) -> Option<(Trade, f64)> {
+        let position = self.strategy.take_position(id)?;
+        let mut trade = active_trades.remove(id)?;
+        trade.exit_fills = closing_fills(&position, chains, mark_price);
+        trade.exit_price = -trade.exit_fills.iter().map(LegFill::cash_flow).sum::<f64>();
+        trade.exit_time = timestamp;
+        trade.exit_reason = Some(reason);
+        if let Some(metadata) = trade.metadata.as_object_mut() {
+            metadata.insert(
+                "exit_underlying_price".to_string(),
+                serde_json::json!(mark_price),
+            );
+            metadata.insert("status".to_string(), serde_json::json!("closed"));
+            metadata.insert("reason".to_string(), serde_json::json!(reason.to_string()));
+        }
+
+        let commission = match reason {
+            ExitReason::Expiration => self.settlement_commission(&trade.exit_fills),
+            _ => self.config.commission.for_fills(&trade.exit_fills),
+        };
+        info!(
+            "Iron Condor EXIT: ID={}, Exit Premium=${:.2}, Reason={}",
+            id, trade.exit_price, reason
+        );
+        let cash_paid = trade.exit_price + commission;
+        Some((trade, cash_paid))
+    }
+
+    /// Underlying price options expiring at `expiration` settle against, seen from the
+    /// first event at or after it. PM cash settlement uses the close, the last mark
+    /// (`previous_mark`) at or before expiration; the others settle at this event's mark,
+    /// which for AM settlement is the opening print.
+    fn settlement_price(
+        &self,
+        expiration: DateTime<Utc>,
+        previous_mark: Option<(DateTime<Utc>, f64)>,
+        event: &EnhancedMarketEvent,
+    ) -> f64 {
+        match self.config.product.settlement {
+            SettlementType::CashPmSettled => previous_mark
+                .filter(|(timestamp, _)| *timestamp <= expiration)
+                .map_or(event.mark_price(), |(_, price)| price),
+            SettlementType::Physical | SettlementType::CashAmSettled => event.mark_price(),
+        }
+    }
+
+    /// Fees on settling `fills` at expiration. Cash-settled options pay out without an
+    /// assignment or exercise, so only physically settled legs are charged.
+    fn settlement_commission(&self, fills: &[LegFill]) -> f64 {
+        if self.config.product.settlement.is_cash_settled() {
+            0.0
+        } else {
+            self.config.commission.for_settlement(fills)
+        }
+    }
+
+    /// Copy of `event`'s chain for `expiration` when chains are being recorded
+    fn recorded_chain(
+        &self,
+        event: &EnhancedMarketEvent,
+        expiration: DateTime<Utc>,
+    ) -> Option<OptionsChain> {
+        if !self.config.record_chains {
+            return None;
+        }
+        event
+            .options_chains
+            .values()
+            .find(|chain| chain.expiration == expiration)
+            .cloned()
+    }
+
+    /// Capital held against an open position: the margin requirement, or the full
+    /// collateral in cash-secured mode
+    fn capital_requirement(&self, position: &IronCondorPosition) -> f64 {
+        if self.config.cash_secured {
+            position.cash_secured_requirement()
+        } else {
+            position.margin_requirement()
+        }
+    }
+
+    /// The chain as the strategy should see it: widened near expiration when configured
+    fn quoted_chain<'a>(&self, chain: &'a OptionsChain) -> Cow<'a, OptionsChain> {
+        if !self.config.expiry_spread_widening {
+            return Cow::Borrowed(chain);
+        }
+        let mut quoted = chain.clone();
+        quoted.widen_spreads_for_expiry();
+        Cow::Owned(quoted)
+    }
+
+    /// Fill a new entry `fill_mid_offset` of the half-spread from the mid, in the signal
+    /// and in the strategy's copy of the position, so its exits fill the same way
+    fn with_fill_offset(&mut self, signal: IronCondorSignal) -> IronCondorSignal {
+        let offset = self.config.fill_mid_offset;
+        match signal {
+            IronCondorSignal::Enter {
+                mut position,
+                timestamp,
+            } if offset != 1.0 => {
+                position.fill_mid_offset = Some(offset);
+                position.entry_premium = net_premium(&position.legs());
+                self.strategy
+                    .state
+                    .active_positions
+                    .insert(position.id, (*position).clone());
+                IronCondorSignal::Enter {
+                    position,
+                    timestamp,
+                }
+            }
+            signal => signal,
+        }
+    }
+
+    /// Open the configured JSON-lines file for appending. A file that can't be opened
+    /// disables the output rather than aborting the run.
+    fn open_jsonl_output(&self) -> Option<File> {
+        let path = self.jsonl_output.as_ref()?;
+        match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                warn!("Can't open JSON-lines output {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Fixed-width table of the last `last_n` trades: strikes, entry/exit premium, P&L,
+    /// exit reason and holding duration
+    pub fn trade_blotter_string(&self, last_n: usize) -> String {
+        let mut blotter = format!(
+            "{:<16} {:<23} {:>8} {:>8} {:>10} {:<20} {:>8}\n",
+            "Entry", "Strikes (P/P/C/C)", "Credit", "Debit", "P&L", "Reason", "Held"
+        );
+
+        let skip = self.trades.len().saturating_sub(last_n);
+        for trade in self.trades.iter().skip(skip) {
+            let strike = |key: &str| trade.metadata[key].as_f64().unwrap_or_default();
+            let strikes = format!(
+                "{}/{}/{}/{}",
+                strike("long_put_strike"),
+                strike("short_put_strike"),
+                strike("short_call_strike"),
+                strike("long_call_strike")
+            );
+            let (debit, pnl, reason) = if trade.exit_fills.is_empty() {
+                ("-".to_string(), "-".to_string(), "open")
+            } else {
+                let pnl = (trade.entry_price - trade.exit_price) * 100.0;
+                (
+                    format!("{:.2}", trade.exit_price),
+                    format!("{}${:.2}", if pnl < 0.0 { "-" } else { "" }, pnl.abs()),
+                    trade.metadata["reason"].as_str().unwrap_or("-"),
+                )
+            };
+            let held = trade.exit_time - trade.entry_time;
+
+            blotter.push_str(&format!(
+                "{:<16} {:<23} {:>8.2} {:>8} {:>10} {:<20} {:>8}\n",
+                trade.entry_time.format("%Y-%m-%d %H:%M"),
+                strikes,
+                trade.entry_price,
+                debit,
+                pnl,
+                reason,
+                format!("{}d {}h", held.num_days(), held.num_hours() % 24)
+            ));
+        }
+        blotter
+    }
+
+    /// Markdown report of a finished run: the config summary, the metrics table and the
+    /// last `last_n` trades of the blotter
+    #[allow(dead_code)]
+    pub fn markdown_report(&self, metrics: &BacktestMetrics, last_n: usize) -> String {
+        let mut report = String::from("# Backtest Report\n\n");
+        report.push_str(&format!(
+            "- Symbol: {}\n- Period: {} to {}\n- Initial capital: ${:.2}\n\
+             - Commission: ${:.2} per contract\n- Slippage: {:.2}%\n\n",
+            self.strategy.config.symbol,
+            self.config.start_date.format("%Y-%m-%d"),
+            self.config.end_date.format("%Y-%m-%d"),
+            self.config.initial_capital,
+            self.config.commission.per_contract,
+            self.config.slippage_pct * 100.0
+        ));
+        report.push_str("## Metrics\n\n");
+        report.push_str(&metrics.to_markdown());
+        if !self.trades.is_empty() {
+            report.push_str("\n## Trades\n\n```\n");
+            report.push_str(&self.trade_blotter_string(last_n));
+            report.push_str("```\n");
+        }
+        report
+    }
+
+    /// Open positions with their marks as of the last processed event
+    #[allow(dead_code)]
+    pub fn open_positions_snapshot(&self) -> Vec<PositionSnapshot> {
+        let (Some(mark_price), Some(now)) = (self.last_mark_price, self.last_event_time) else {
+            return Vec::new();
+        };
+
+        self.strategy
+            .state
+            .active_positions
+            .values()
+            .map(|position| {
+                let unrealized_pnl = position.calculate_pnl(mark_price);
+                PositionSnapshot {
+                    id: position.id,
+                    short_call_strike: position.short_call.strike,
+                    long_call_strike: position.long_call.strike,
+                    short_put_strike: position.short_put.strike,
+                    long_put_strike: position.long_put.strike,
+                    entry_premium: position.entry_premium,
+                    mark: position.entry_premium - unrealized_pnl,
+                    unrealized_pnl,
+                    dte: position.days_to_expiration(now),
+                }
+            })
+            .collect()
+    }
+
+    /// Cash plus what closing every open position at `chain`'s quotes would pay: what the
+    /// book would be worth if it were flattened now
+    #[allow(dead_code)]
+    pub fn net_liquidation_value(&self, chain: &OptionsChain) -> f64 {
+        let open_value: f64 = self
+            .strategy
+            .state
+            .active_positions
+            .values()
+            .map(|position| {
+                liquidation_value(position, std::iter::once(chain), chain.underlying_price)
+            })
+            .sum();
+        self.current_capital + open_value
+    }
+
+    /// Percentage below the running equity peak at each equity-curve point
+    /// (0 at new highs, negative while in drawdown)
+    #[allow(dead_code)]
+    pub fn underwater_series(&self) -> Vec<(DateTime<Utc>, f64)> {
+        let mut peak = f64::MIN;
+
+        self.equity_curve
+            .iter()
+            .map(|(&timestamp, point)| {
+                let equity = point.gross;
+                peak = peak.max(equity);
+                let underwater_pct = if peak > 0.0 {
+                    (equity - peak) / peak * 100.0
+                } else {
+                    0.0
+                };
+                (timestamp, underwater_pct)
+            })
+            .collect()
+    }
+}
+
+/// Net premium of closing `position` at the quotes of the chain in `chains` with its
+/// expiration, or minus its settlement cost at `spot` when no chain quotes every leg
+fn liquidation_value<'a>(
+    position: &IronCondorPosition,
+    chains: impl Iterator<Item = &'a OptionsChain>,
+    spot: f64,
+) -> f64 {
+    closing_fills(position, chains, spot)
+        .iter()
+        .map(LegFill::cash_flow)
+        .sum()
+}
+
+/// Fills closing `position` at the quotes of the chain in `chains` with its expiration,
+/// or at intrinsic value with the underlying at `spot` when no chain quotes every leg
+fn closing_fills<'a>(
+    position: &IronCondorPosition,
+    mut chains: impl Iterator<Item = &'a OptionsChain>,
+    spot: f64,
+) -> Vec<LegFill> {
+    chains
+        .find(|chain| chain.expiration == position.short_call.expiration)
+        .and_then(|chain| {
+            let mid_offset = position.fill_mid_offset.unwrap_or(1.0);
+            requote_fills(&position.exit_fills(), chain, mid_offset)
+        })
+        .unwrap_or_else(|| position.settlement_fills(spot))
+}
+
+/// The same trades filled at `chain`'s current quotes, `mid_offset` of the half-spread
+/// from the mid. None if a leg isn't quoted.
+fn requote_fills(fills: &[LegFill], chain: &OptionsChain, mid_offset: f64) -> Option<Vec<LegFill>> {
+    fills
+        .iter()
+        .map(|fill| {
+            let contract = match fill.option_type {
+                OptionType::Call => chain.get_call(fill.strike),
+                OptionType::Put => chain.get_put(fill.strike),
+            }?;
+            let side = if fill.is_sell { Side::Sell } else { Side::Buy };
+            Some(
+                Leg {
+                    contract,
+                    side,
+                    quantity: fill.quantity,
+                    mid_offset,
+                }
+                .fill(),
+            )
+        })
+        .collect()
+}
+
+/// Realized volatility of the underlying prices seen while a position was open,
+/// annualized by the average spacing between their timestamps. None when they all share
+/// one timestamp.
+fn holding_realized_vol(prices: &[(DateTime<Utc>, f64)]) -> Option<f64> {
+    let span = (prices.last()?.0 - prices.first()?.0).num_seconds() as f64;
+    if span <= 0.0 {
+        return None;
+    }
+    let spacing = span / (prices.len() - 1) as f64;
+    let periods_per_year = 365.0 * 86_400.0 / spacing;
+    let prices: Vec<f64> = prices.iter().map(|(_, price)| *price).collect();
+    math::realized_volatility(&prices, periods_per_year)
+}
+
+/// Append one JSON line and flush it right away so readers tailing the file see it
+fn write_jsonl(output: &mut Option<File>, line: Option<&serde_json::Value>) {
+    if let (Some(file), Some(line)) = (output.as_mut(), line)
+        && let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush())
+    {
+        warn!("Failed to write JSON-lines output: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::options_data::OptionType;
+    use crate::strategies::iron_condor::{IronCondorConfig, ProfitTarget};
+    use crate::test_fixtures::create_test_event;
+    use chrono::TimeZone;
+
+    fn create_test_runner(
+        config: BacktestConfig,
+        events: Vec<EnhancedMarketEvent>,
+    ) -> BacktestRunner {
+        let market_generator = HistoricalMarketGenerator::new("SPY".to_string(), events);
+        let strategy = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        BacktestRunner::new(config, market_generator, strategy)
+    }
+
+    #[tokio::test]
+    async fn test_estimated_price_events_are_skipped() {
+        let mut event = create_test_event(400.0, Utc::now());
+        event.underlying_price_estimated = true;
+
+        let config = BacktestConfig {
+            skip_estimated_prices: true,
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, vec![event]);
+        runner.run().await;
+
+        assert!(runner.trades.is_empty());
+        assert!(runner.strategy.state.active_positions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_estimated_price_events_trade_when_not_skipped() {
+        let mut event = create_test_event(400.0, Utc::now());
+        event.underlying_price_estimated = true;
+
+        let mut runner = create_test_runner(BacktestConfig::default(), vec![event]);
+        runner.run().await;
+
+        assert_eq!(runner.trades.len(), 1);
+    }
+
+    #[test]
+    fn test_mark_price_ignores_chain_prices() {
+        let mut event = create_test_event(400.0, Utc::now());
+        let stale_chain = OptionsChain {
+            underlying_price: 390.0,
+            ..event.options_chains.values().next().unwrap().clone()
+        };
+        event
+            .options_chains
+            .insert("stale".to_string(), stale_chain);
+        event.underlying_price = 405.0;
+
+        let chain_prices: Vec<f64> = event
+            .options_chains
+            .values()
+            .map(|chain| chain.underlying_price)
+            .collect();
+        assert!(chain_prices.contains(&400.0) && chain_prices.contains(&390.0));
+        assert_eq!(event.mark_price(), 405.0);
+    }
+
+    #[tokio::test]
+    async fn test_runner_marks_positions_at_event_price() {
+        let start = Utc::now();
+        let entry = create_test_event(400.0, start);
+
+        // Chain still says 400, but the event's price has blown through the call wing
+        let mut shocked = create_test_event(400.0, start + Duration::hours(1));
+        shocked.underlying_price = 460.0;
+
+        let mut runner = create_test_runner(BacktestConfig::default(), vec![entry, shocked]);
+        runner.run().await;
+
+        assert_eq!(runner.trades.len(), 1);
+        assert_eq!(runner.strategy.state.current_price, 460.0);
+        assert!(runner.strategy.state.active_positions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hold_to_expiry_shadow_vs_managed() {
+        let start = Utc::now();
+        let entry = create_test_event(400.0, start);
+
+        // Price blows through the call wing shortly after entry and stays there
+        let mut shocked = create_test_event(400.0, start + Duration::hours(1));
+        shocked.underlying_price = 460.0;
+
+        // Observation after expiration without chains, so no new entry is attempted
+        let mut settlement = create_test_event(460.0, start + Duration::days(31));
+        settlement.options_chains.clear();
+
+        let config = BacktestConfig {
+            hold_to_expiry_shadow: true,
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, vec![entry, shocked, settlement]);
+        let metrics = runner.run().await;
+
+        let hold_return = metrics.hold_to_expiry_return_pct.unwrap();
+        assert!(metrics.total_return_pct > hold_return);
+    }
+
+    #[tokio::test]
+    async fn test_hold_to_expiry_shadow_disabled_by_default() {
+        let events = vec![create_test_event(400.0, Utc::now())];
+        let mut runner = create_test_runner(BacktestConfig::default(), events);
+        let metrics = runner.run().await;
+
+        assert_eq!(metrics.hold_to_expiry_return_pct, None);
+    }
+
+    #[test]
+    fn test_underwater_series() {
+        let start = Utc::now();
+        let mut runner = create_test_runner(BacktestConfig::default(), Vec::new());
+        for (day, equity) in [100.0, 110.0, 99.0, 104.5, 120.0].into_iter().enumerate() {
+            runner.equity_curve.insert(
+                start + Duration::days(day as i64),
+                EquityPoint {
+                    gross: equity,
+                    free: equity,
+                },
+            );
+        }
+
+        let underwater: Vec<f64> = runner
+            .underwater_series()
+            .into_iter()
+            .map(|(_, pct)| pct)
+            .collect();
+
+        assert_eq!(underwater.len(), 5);
+        assert_eq!(underwater[0], 0.0);
+        assert_eq!(underwater[1], 0.0);
+        assert!((underwater[2] - -10.0).abs() < 1e-9);
+        assert!((underwater[3] - -5.0).abs() < 1e-9);
+        assert_eq!(underwater[4], 0.0);
+    }
+
+    #[test]
+    fn test_min_and_max_dte() {
+        let now = Utc::now();
+        let mut event = create_test_event(400.0, now);
+        let template = event.options_chains.values().next().unwrap().clone();
+
+        event.options_chains.clear();
+        for days in [3, 10, 45] {
+            let chain = OptionsChain {
+                expiration: now + Duration::days(days),
+                ..template.clone()
+            };
+            event.options_chains.insert(days.to_string(), chain);
+        }
+
+        assert_eq!(event.min_dte(now), Some(3));
+        assert_eq!(event.max_dte(now), Some(45));
+
+        event.options_chains.clear();
+        assert_eq!(event.min_dte(now), None);
+        assert_eq!(event.max_dte(now), None);
+    }
+
+    fn create_daily_events(days: i64) -> Vec<EnhancedMarketEvent> {
+        let start = Utc::now();
+        (0..days)
+            .map(|day| create_test_event(400.0, start + Duration::days(day)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fill_probability_is_seeded_and_deterministic() {
+        let config = BacktestConfig {
+            fill_probability: 0.5,
+            random_seed: 7,
+            ..Default::default()
+        };
+
+        let mut first = create_test_runner(config.clone(), create_daily_events(60));
+        let first_metrics = first.run().await;
+        let mut second = create_test_runner(config, create_daily_events(60));
+        let second_metrics = second.run().await;
+
+        assert_eq!(
+            first_metrics.unfilled_entries,
+            second_metrics.unfilled_entries
+        );
+        assert_eq!(first.trades.len(), second.trades.len());
+        assert!(first_metrics.unfilled_entries > 0);
+        assert!(!first.trades.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fill_probability_extremes() {
+        let never = BacktestConfig {
+            fill_probability: 0.0,
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(never, create_daily_events(10));
+        let metrics = runner.run().await;
+        assert!(runner.trades.is_empty());
+        // An unfilled entry doesn't start the re-entry timer: every day tries again
+        assert_eq!(metrics.unfilled_entries, 10);
+        assert_eq!(runner.strategy.state.last_signal, None);
+        assert!(runner.strategy.state.active_positions.is_empty());
+
+        let mut runner = create_test_runner(BacktestConfig::default(), create_daily_events(10));
+        let metrics = runner.run().await;
+        assert!(!runner.trades.is_empty());
+        assert_eq!(metrics.unfilled_entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_open_positions_snapshot_after_single_event() {
+        let now = Utc::now();
+        let mut runner = create_test_runner(
+            BacktestConfig::default(),
+            vec![create_test_event(400.0, now)],
+        );
+        assert!(runner.open_positions_snapshot().is_empty());
+
+        runner.run().await;
+        let snapshot = runner.open_positions_snapshot();
+
+        assert_eq!(snapshot.len(), 1);
+        let position = &snapshot[0];
+        assert!(position.short_call_strike > 400.0 && position.short_put_strike < 400.0);
+        assert!(position.long_call_strike > position.short_call_strike);
+        assert!(position.long_put_strike < position.short_put_strike);
+        assert!(position.entry_premium > 0.0);
+        // Spot sits between the shorts, so the expiration-style mark is fully in our favor
+        assert_eq!(position.unrealized_pnl, position.entry_premium);
+        assert_eq!(position.mark, 0.0);
+        assert_eq!(position.dte, 30);
+    }
+
+    fn create_events_with_dtes(days: i64, dtes: &[i64]) -> Vec<EnhancedMarketEvent> {
+        let start = Utc::now();
+        (0..days)
+            .map(|day| {
+                let timestamp = start + Duration::days(day);
+                let mut event = create_test_event(400.0, timestamp);
+                let template = event.options_chains.values().next().unwrap().clone();
+                event.options_chains.clear();
+                for &dte in dtes {
+                    let mut chain = template.clone();
+                    chain.expiration = timestamp + Duration::days(dte);
+                    for contract in chain.calls.values_mut().chain(chain.puts.values_mut()) {
+                        contract.expiration = chain.expiration;
+                    }
+                    event
+                        .options_chains
+                        .insert(chain.expiration.format("%Y-%m-%d").to_string(), chain);
+                }
+                event
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_no_entry_without_expiration_near_target_dte() {
+        let run_with_target = |target_dte| async move {
+            let market_generator = HistoricalMarketGenerator::new(
+                "SPY".to_string(),
+                create_events_with_dtes(5, &[45, 60]),
+            );
+            let strategy = IronCondorSignalGenerator::new(IronCondorConfig {
+                target_dte: Some(target_dte),
+                max_dte_deviation: 5,
+                ..Default::default()
+            });
+            let mut runner =
+                BacktestRunner::new(BacktestConfig::default(), market_generator, strategy);
+            runner.run().await;
+            runner.trades.len()
+        };
+
+        // Only 45 and 60 DTE are listed, so a 30 DTE target must not fall back to 45
+        assert_eq!(run_with_target(30).await, 0);
+        // A target that is listed trades normally
+        assert!(run_with_target(45).await > 0);
+    }
+
+    #[tokio::test]
+    async fn test_free_capital_reserves_margin_while_open() {
+        let mut runner = create_test_runner(BacktestConfig::default(), create_daily_events(5));
+        runner.run().await;
+
+        let trade = &runner.trades[0];
+        let width = trade.metadata["long_call_strike"].as_f64().unwrap()
+            - trade.metadata["short_call_strike"].as_f64().unwrap();
+        let points: Vec<EquityPoint> = runner.equity_curve.values().copied().collect();
+
+        // Before entry nothing is reserved
+        assert_eq!(points[0].free, points[0].gross);
+        assert_eq!(points[0].gross, runner.config.initial_capital);
+        // While open, free capital is below gross by the margin requirement
+        assert!((points[1].gross - points[1].free - width).abs() < 1e-9);
+        // After the close the margin is released
+        assert!(!trade.exit_fills.is_empty());
+        assert_eq!(points[3].free, points[3].gross);
+    }
+
+    #[tokio::test]
+    async fn test_mae_and_mfe_track_price_extremes() {
+        let start = Utc::now();
+        let events = [400.0, 423.0, 395.0]
+            .into_iter()
+            .enumerate()
+            .map(|(day, price)| create_test_event(price, start + Duration::days(day as i64)))
+            .collect();
+        let strategy = IronCondorSignalGenerator::new(IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(10.0),
+            stop_loss_pct: 10.0, // Only the time exit closes the position
+            ..Default::default()
+        });
+        let mut runner = BacktestRunner::new(
+            BacktestConfig::default(),
+            HistoricalMarketGenerator::new("SPY".to_string(), events),
+            strategy,
+        );
+        let metrics = runner.run().await;
+
+        let trade = &runner.trades[0];
+        let premium = trade.entry_price;
+        // 423 is $3 through the 420 short call; 395 is back inside the short strikes
+        assert!((trade.mae - (premium - 3.0)).abs() < 1e-9);
+        assert!((trade.mfe - premium).abs() < 1e-9);
+        assert!((metrics.avg_mae - trade.mae * 100.0).abs() < 1e-9);
+        assert!((metrics.avg_mfe - trade.mfe * 100.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_max_events_caps_processing() {
+        let config = BacktestConfig {
+            max_events: Some(10),
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, create_daily_events(100));
+        let metrics = runner.run().await;
+
+        assert_eq!(runner.market_generator.current_idx, 10);
+        assert_eq!(runner.equity_curve.len(), 10);
+
+        // Same result as a dataset that only had those 10 events
+        let mut truncated = create_test_runner(BacktestConfig::default(), create_daily_events(10));
+        let truncated_metrics = truncated.run().await;
+        assert_eq!(metrics.total_trades, truncated_metrics.total_trades);
+        assert_eq!(metrics.final_capital, truncated_metrics.final_capital);
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_output_appends_one_line_per_executed_signal() {
+        let path = std::env::temp_dir().join(format!("signals-{}.jsonl", Uuid::new_v4()));
+
+        let mut runner = create_test_runner(BacktestConfig::default(), create_daily_events(10))
+            .with_jsonl_output(&path);
+        runner.run().await;
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let closed = runner
+            .trades
+            .iter()
+            .filter(|trade| !trade.exit_fills.is_empty())
+            .count();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(closed > 0);
+        assert_eq!(lines.len(), runner.trades.len() + closed);
+        assert!(lines.iter().all(|line| line["timestamp"].is_string()));
+        assert!(lines[0]["signal"].get("Enter").is_some());
+        assert!(lines[1]["signal"].get("Exit").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_close_open_at_end_realizes_open_positions() {
+        let now = Utc::now();
+        let events = vec![
+            create_test_event(400.0, now),
+            create_test_event(421.0, now + Duration::hours(6)),
+        ];
+
+        let mut left_open = create_test_runner(BacktestConfig::default(), events.clone());
+        left_open.run().await;
+        assert_eq!(left_open.trades.len(), 1);
+        assert_eq!(left_open.trades[0].metadata["status"], "open");
+        assert!(left_open.trades[0].exit_fills.is_empty());
+
+        let config = BacktestConfig {
+            close_open_at_end: true,
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, events);
+        let metrics = runner.run().await;
+
+        assert_eq!(runner.trades.len(), 1);
+        let trade = &runner.trades[0];
+        assert_eq!(trade.metadata["status"], "closed");
+        assert_eq!(trade.metadata["reason"], "end of backtest");
+        assert_eq!(trade.exit_time, now + Duration::hours(6));
+        // Settled at intrinsic with the underlying 1 point through the short call
+        assert_eq!(trade.metadata["short_call_strike"], 420.0);
+        assert!((trade.exit_price - 1.0).abs() < 1e-9);
+        assert!(runner.strategy.state.active_positions.is_empty());
+
+        let commission = runner.config.commission.for_fills(&trade.entry_fills)
+            + runner.config.commission.for_fills(&trade.exit_fills);
+        let expected_capital = 100_000.0 + trade.entry_price - trade.exit_price - commission;
+        assert!((metrics.final_capital - expected_capital).abs() < 1e-9);
+        assert_eq!(metrics.total_trades, 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_position_at_end_is_not_scored() {
+        let now = Utc::now();
+        let events = vec![
+            create_test_event(400.0, now),
+            create_test_event(421.0, now + Duration::hours(6)),
+        ];
+
+        let mut runner = create_test_runner(BacktestConfig::default(), events);
+        let metrics = runner.run().await;
+
+        // The open trade's zero exit price would otherwise score its whole credit as a win
+        assert_eq!(runner.trades.len(), 1);
+        assert_eq!(runner.trades[0].metadata["status"], "open");
+        assert_eq!(metrics.total_trades, 0);
+        assert_eq!(metrics.winning_trades, 0);
+        assert_eq!(metrics.win_rate_pct, 0.0);
+        assert_eq!(metrics.profit_factor, 0.0);
+        assert_eq!(metrics.win_rate_p_value(), None);
+    }
+
+    #[tokio::test]
+    async fn test_trade_blotter_lists_last_trades() {
+        let mut runner = create_test_runner(BacktestConfig::default(), create_daily_events(40));
+        runner.run().await;
+        assert!(runner.trades.len() > 3);
+
+        let blotter = runner.trade_blotter_string(3);
+        let lines: Vec<&str> = blotter.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("Entry"));
+        assert!(lines[0].contains("P&L") && lines[0].contains("Reason"));
+        let last = runner.trades.last().unwrap();
+        assert!(lines[3].contains(&format!("{:.2}", last.entry_price)));
+
+        // Asking for more rows than trades lists them all
+        let everything = runner.trade_blotter_string(usize::MAX);
+        assert_eq!(everything.lines().count(), runner.trades.len() + 1);
+    }
+
+    #[tokio::test]
+    async fn test_daily_equity_granularity_keeps_one_point_per_day() {
+        let start = DateTime::parse_from_rfc3339("2025-01-06T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let events: Vec<EnhancedMarketEvent> = (0..12)
+            .map(|step| create_test_event(400.0, start + Duration::hours(step * 6)))
+            .collect();
+
+        let mut every_event = create_test_runner(BacktestConfig::default(), events.clone());
+        every_event.run().await;
+        assert_eq!(every_event.equity_curve.len(), 12);
+
+        let config = BacktestConfig {
+            equity_granularity: EquityGranularity::Daily,
+            ..Default::default()
+        };
+        let mut daily = create_test_runner(config, events);
+        daily.run().await;
+
+        let days: Vec<DateTime<Utc>> = daily.equity_curve.keys().copied().collect();
+        assert_eq!(
+            days,
+            vec![start, start + Duration::days(1), start + Duration::days(2)]
+        );
+        // Each day keeps its last sample
+        let last_of_first_day = every_event.equity_curve[&(start + Duration::hours(18))];
+        assert_eq!(daily.equity_curve[&start], last_of_first_day);
+    }
+
+    #[test]
+    fn test_select_expiration_prefers_liquid_chain_among_similar_dtes() {
+        let mut event = create_events_with_dtes(1, &[7, 9, 30]).remove(0);
+        // The 9 DTE monthly carries ten times the weekly's open interest
+        let monthly_key = (event.timestamp + Duration::days(9))
+            .format("%Y-%m-%d")
+            .to_string();
+        for contract in event
+            .options_chains
+            .get_mut(&monthly_key)
+            .map(|chain| chain.calls.values_mut().chain(chain.puts.values_mut()))
+            .unwrap()
+        {
+            contract.open_interest *= 10;
+        }
+        let weekly_key = (event.timestamp + Duration::days(7))
+            .format("%Y-%m-%d")
+            .to_string();
+
+        assert_eq!(
+            event.select_expiration(Some(7), 0),
+            Some(weekly_key.clone())
+        );
+        assert_eq!(
+            event.select_expiration(Some(7), 3),
+            Some(monthly_key.clone())
+        );
+        assert_eq!(event.select_expiration(None, 0), Some(weekly_key));
+        assert_eq!(event.select_expiration(None, 2), Some(monthly_key));
+    }
+
+    #[tokio::test]
+    async fn test_run_range_partitions_loaded_events() {
+        let events = create_daily_events(100);
+        let midpoint = events[50].timestamp;
+        let (first, last) = (events[0].timestamp, events[99].timestamp);
+        let mut runner = create_test_runner(BacktestConfig::default(), events);
+
+        let early = runner
+            .run_range(first, midpoint - Duration::seconds(1))
+            .await;
+        let early_trades = runner.trades.clone();
+        let late = runner.run_range(midpoint, last).await;
+        let late_trades = runner.trades.clone();
+        assert!(early.total_trades > 0 && late.total_trades > 0);
+        assert_eq!(early.total_trades, early_trades.len());
+        assert_eq!(late.total_trades, late_trades.len());
+        assert!(early_trades.iter().all(|trade| trade.entry_time < midpoint));
+        assert!(late_trades.iter().all(|trade| trade.entry_time >= midpoint));
+
+        // The whole dataset is still loaded, and slicing left no state behind
+        assert_eq!(runner.market_generator.events.len(), 100);
+        let full = runner.run_range(first, last).await;
+        let mut fresh = create_test_runner(BacktestConfig::default(), create_daily_events(100));
+        assert_eq!(full.total_trades, fresh.run().await.total_trades);
+    }
+
+    #[tokio::test]
+    async fn test_margin_call_liquidates_open_positions() {
+        let now = Utc::now();
+        // The 420 short call goes 8 in the money: closing costs 8 of the ~16 in cash,
+        // leaving less than the ~9 margin the position holds
+        let events = vec![
+            create_test_event(400.0, now),
+            create_test_event(428.0, now + Duration::hours(1)),
+        ];
+        let config = BacktestConfig {
+            initial_capital: 15.0,
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, events.clone());
+        let metrics = runner.run().await;
+
+        assert_eq!(metrics.margin_calls, 1);
+        assert!(runner.strategy.state.active_positions.is_empty());
+        let trade = &runner.trades[0];
+        assert_eq!(trade.metadata["reason"], "margin call");
+        assert!((trade.exit_price - 8.0).abs() < 1e-9);
+
+        // A well-capitalized account rides out the same move
+        let mut runner = create_test_runner(BacktestConfig::default(), events);
+        assert_eq!(runner.run().await.margin_calls, 0);
+    }
+
+    #[test]
+    fn test_realized_volatility_of_constant_return_path_is_zero() {
+        let start = Utc::now();
+        // The underlying compounds 0.5% every day: every log return is identical
+        let events: Vec<EnhancedMarketEvent> = (0..30)
+            .map(|day| {
+                let price = 400.0 * 1.005_f64.powi(day as i32);
+                create_test_event(price, start + Duration::days(day))
+            })
+            .collect();
+        let generator = HistoricalMarketGenerator::new("SPY".to_string(), events);
+
+        let vols = generator.realized_volatility(20);
+
+        assert_eq!(vols.len(), 10);
+        assert_eq!(vols[0].0, start + Duration::days(20));
+        assert!(vols.iter().all(|(_, vol)| *vol < 1e-9));
+        assert!(generator.realized_volatility(30).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_vol_risk_premium_on_flat_price_is_entry_iv() {
+        let start = Utc::now();
+        // Hourly events at a flat 400: the position is held for the whole run and closed
+        // at the end, with no realized movement against its 20% entry IV
+        let events: Vec<EnhancedMarketEvent> = (0..6)
+            .map(|hour| create_test_event(400.0, start + Duration::hours(hour)))
+            .collect();
+        let config = BacktestConfig {
+            close_open_at_end: true,
+            ..Default::default()
+        };
+        let strategy = IronCondorSignalGenerator::new(IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(2.0),
+            ..Default::default()
+        });
+        let mut runner = BacktestRunner::new(
+            config,
+            HistoricalMarketGenerator::new("SPY".to_string(), events),
+            strategy,
+        );
+        let metrics = runner.run().await;
+
+        assert_eq!(runner.trades.len(), 1);
+        let trade = &runner.trades[0];
+        assert_eq!(trade.entry_iv, Some(0.20));
+        assert!(trade.realized_vol.unwrap() < 1e-9);
+        assert!((metrics.vol_risk_premium.unwrap() - 0.20).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_assigned_position_pays_assignment_fee() {
+        let now = Utc::now();
+        // Entered 30 days out; the next event comes after expiration with the 420 short
+        // call 5 in the money and the 430 long call worthless
+        let events = vec![
+            create_test_event(400.0, now),
+            create_test_event(425.0, now + Duration::days(30) + Duration::hours(1)),
+        ];
+        let run = |assignment_fee| {
+            let config = BacktestConfig {
+                commission: CommissionModel {
+                    assignment_fee,
+                    exercise_fee: 100.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut runner = create_test_runner(config, events.clone());
+            async move {
+                let metrics = runner.run().await;
+                (runner, metrics)
+            }
+        };
+
+        let (runner, free) = run(0.0).await;
+        let trade = &runner.trades[0];
+        assert_eq!(trade.metadata["reason"], "expiration");
+        assert!((trade.exit_price - 5.0).abs() < 1e-9);
+
+        // Only the assigned short call is charged; the worthless long isn't exercised
+        let (_, charged) = run(7.5).await;
+        assert!((free.final_capital - charged.final_capital - 7.5).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_markdown_report_embeds_config_metrics_and_blotter() {
+        let mut runner = create_test_runner(BacktestConfig::default(), create_daily_events(10));
+        let metrics = runner.run().await;
+
+        let report = runner.markdown_report(&metrics, 5);
+
+        assert!(report.starts_with("# Backtest Report\n"));
+        assert!(report.contains("- Symbol: SPY\n"));
+        assert!(report.contains("- Commission: $0.65 per contract\n"));
+        assert!(report.contains(&metrics.to_markdown()));
+        assert!(report.contains(&runner.trade_blotter_string(5)));
+    }
+
+    #[tokio::test]
+    async fn test_fill_delay_executes_at_next_event_quotes() {
+        let now = Utc::now();
+        let signal_event = create_test_event(400.0, now);
+        // An hour later with richer call quotes, so the two events price the condor apart
+        let mut fill_event = signal_event.clone();
+        fill_event.timestamp = now + Duration::hours(1);
+        for chain in fill_event.options_chains.values_mut() {
+            chain.timestamp = fill_event.timestamp;
+            for call in chain.calls.values_mut() {
+                call.bid *= 1.5;
+                call.ask *= 1.5;
+            }
+        }
+        let premium_at = |event: &EnhancedMarketEvent| {
+            let chain = event.options_chains.values().next().unwrap();
+            chain.get_call(420.0).unwrap().bid - chain.get_call(430.0).unwrap().ask
+                + chain.get_put(380.0).unwrap().bid
+                - chain.get_put(370.0).unwrap().ask
+        };
+
+        let run = |fill_delay_events| {
+            let config = BacktestConfig {
+                fill_delay_events,
+                close_open_at_end: true,
+                ..Default::default()
+            };
+            let strategy = IronCondorSignalGenerator::new(IronCondorConfig {
+                profit_target: ProfitTarget::PctOfMax(2.0),
+                ..Default::default()
+            });
+            let mut runner = BacktestRunner::new(
+                config,
+                HistoricalMarketGenerator::new(
+                    "SPY".to_string(),
+                    vec![signal_event.clone(), fill_event.clone()],
+                ),
+                strategy,
+            );
+            async move {
+                runner.run().await;
+                runner.trades
+            }
+        };
+
+        let immediate = run(0).await;
+        assert!((immediate[0].entry_price - premium_at(&signal_event)).abs() < 1e-9);
+
+        let delayed = run(1).await;
+        assert_eq!(delayed.len(), 1);
+        assert_eq!(delayed[0].entry_time, fill_event.timestamp);
+        assert!((delayed[0].entry_price - premium_at(&fill_event)).abs() < 1e-9);
+        assert!((premium_at(&fill_event) - premium_at(&signal_event)).abs() > 0.1);
+    }
+
+    #[tokio::test]
+    async fn test_fill_mid_offset_fills_halfway_to_the_worst_edge() {
+        let event = create_test_event(400.0, Utc::now());
+        let chain = event.options_chains.values().next().unwrap().clone();
+        let legs = [
+            chain.get_call(420.0).unwrap(),
+            chain.get_call(430.0).unwrap(),
+            chain.get_put(380.0).unwrap(),
+            chain.get_put(370.0).unwrap(),
+        ];
+        let mid_premium = legs[0].mid() - legs[1].mid() + legs[2].mid() - legs[3].mid();
+        let worst_premium = legs[0].bid - legs[1].ask + legs[2].bid - legs[3].ask;
+        assert!(mid_premium > worst_premium);
+
+        let config = BacktestConfig {
+            fill_mid_offset: 0.5,
+            close_open_at_end: true,
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, vec![event]);
+        runner.run().await;
+
+        let trade = &runner.trades[0];
+        let expected = (mid_premium + worst_premium) / 2.0;
+        assert!((trade.entry_price - expected).abs() < 1e-9);
+        // The fills keep the chain's own quotes, so slippage is measured from the real mid
+        let short_call = &trade.entry_fills[0];
+        assert_eq!((short_call.bid, short_call.ask), (legs[0].bid, legs[0].ask));
+        let half_slippage = (mid_premium - worst_premium) / 2.0;
+        let slippage: f64 = trade.entry_fills.iter().map(LegFill::slippage).sum();
+        assert!((slippage - half_slippage).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_net_liquidation_value_marks_open_positions() {
+        let event = create_test_event(400.0, Utc::now());
+        let strategy = IronCondorSignalGenerator::new(IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(2.0),
+            ..Default::default()
+        });
+        let config = BacktestConfig {
+            commission: CommissionModel {
+                per_contract: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut runner = BacktestRunner::new(
+            config,
+            HistoricalMarketGenerator::new("SPY".to_string(), vec![event.clone()]),
+            strategy,
+        );
+        runner.run().await;
+        assert_eq!(runner.strategy.state.active_positions.len(), 1);
+        let initial_capital = runner.config.initial_capital;
+        let cash = runner.current_capital;
+        assert!(cash > initial_capital);
+
+        // Flattening at the entry quotes gives back the credit plus the spread
+        let entry_chain = event.options_chains.values().next().unwrap();
+        let at_entry = runner.net_liquidation_value(entry_chain);
+        assert!(at_entry < initial_capital);
+
+        // Once the options have decayed, closing costs a fraction of the credit
+        let mut decayed = entry_chain.clone();
+        for contract in decayed.calls.values_mut().chain(decayed.puts.values_mut()) {
+            contract.bid *= 0.1;
+            contract.ask *= 0.1;
+        }
+        let net_liq = runner.net_liquidation_value(&decayed);
+        assert!(net_liq > at_entry);
+        assert!(net_liq > initial_capital && net_liq < cash);
+
+        // Without a chain for the position's expiration it's valued at intrinsic
+        let other_expiry = OptionsChain {
+            expiration: decayed.expiration + Duration::days(7),
+            ..decayed
+        };
+        assert_eq!(runner.net_liquidation_value(&other_expiry), cash);
+    }
+
+    #[tokio::test]
+    async fn test_cash_secured_mode_fits_fewer_positions() {
+        // 15 covers the 10 margin on the wider spread but not the 20 securing both
+        let open_positions = |cash_secured| {
+            let config = BacktestConfig {
+                initial_capital: 15.0,
+                cash_secured,
+                ..Default::default()
+            };
+            let mut runner = create_test_runner(config, vec![create_test_event(400.0, Utc::now())]);
+            async move {
+                runner.run().await;
+                runner.strategy.state.active_positions.len()
+            }
+        };
+
+        assert_eq!(open_positions(false).await, 1);
+        assert_eq!(open_positions(true).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_recorded_chains_match_entry_and_exit_events() {
+        let now = Utc::now();
+        // Same expiration an hour later with the underlying up to 402
+        let entry_event = create_test_event(400.0, now);
+        let mut exit_event = entry_event.clone();
+        exit_event.timestamp = now + Duration::hours(1);
+        exit_event.underlying_price = 402.0;
+        for chain in exit_event.options_chains.values_mut() {
+            chain.timestamp = exit_event.timestamp;
+            chain.underlying_price = 402.0;
+        }
+        let events = vec![entry_event, exit_event];
+        let config = BacktestConfig {
+            record_chains: true,
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, events.clone());
+        runner.run().await;
+
+        let trade = &runner.trades[0];
+        assert_eq!(trade.exit_time, now + Duration::hours(1));
+        assert_eq!(trade.entry_chain.as_ref().unwrap().underlying_price, 400.0);
+        assert_eq!(trade.exit_chain.as_ref().unwrap().underlying_price, 402.0);
+
+        let mut runner = create_test_runner(BacktestConfig::default(), events);
+        runner.run().await;
+        assert!(runner.trades[0].entry_chain.is_none() && runner.trades[0].exit_chain.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_event_liquidity_totals() {
+        // 13 strikes x 2 types, each with 1000 open interest and 100 volume
+        let event = create_test_event(400.0, Utc::now());
+        assert_eq!(event.total_open_interest(), 26_000);
+        assert_eq!(event.total_volume(), 2_600);
+        assert!((event.liquidity_score() - 2_000.0).abs() < 1e-9);
+
+        let thin = BacktestConfig {
+            min_liquidity_score: Some(2_500.0),
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(thin, vec![event]);
+        runner.run().await;
+        assert!(runner.trades.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_daily_profit_target_closes_the_whole_book() {
+        let now = Utc::now();
+        let entry_event = create_test_event(400.0, now);
+        let config = BacktestConfig {
+            commission: CommissionModel {
+                per_contract: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, Vec::new());
+        let initial_capital = runner.config.initial_capital;
+        let chain = entry_event.options_chains.values().next().unwrap().clone();
+
+        // The strategy holds one position at a time, so set the first aside for the second
+        let enter = |runner: &mut BacktestRunner| {
+            runner.strategy.state.last_signal = None;
+            match runner
+                .strategy
+                .generate_signal_with_mark_price(&chain, 400.0)
+            {
+                Some(IronCondorSignal::Enter { position, .. }) => *position,
+                _ => panic!("Expected an entry"),
+            }
+        };
+        let first = enter(&mut runner);
+        runner.strategy.take_position(&first.id);
+        let second = enter(&mut runner);
+        runner
+            .strategy
+            .state
+            .active_positions
+            .insert(first.id, first.clone());
+
+        // Two hours later the options have lost most of their value
+        let mut decayed_event = entry_event.clone();
+        decayed_event.timestamp = now + Duration::hours(2);
+        for chain in decayed_event.options_chains.values_mut() {
+            chain.timestamp = decayed_event.timestamp;
+            for contract in chain.calls.values_mut().chain(chain.puts.values_mut()) {
+                contract.bid *= 0.1;
+                contract.ask *= 0.1;
+            }
+        }
+        let position_profit = |position: &IronCondorPosition| {
+            position.entry_premium
+                + liquidation_value(position, decayed_event.options_chains.values(), 400.0)
+        };
+        assert!((position_profit(&first) - position_profit(&second)).abs() < 1e-9);
+        runner.config.daily_profit_target = Some(1.5 * position_profit(&first));
+
+        // Either position alone falls short of the target
+        let mut only_first =
+            HashMap::from([(first.id, runner.open_trade(&first, &entry_event, now))]);
+        let cash = initial_capital + first.entry_premium;
+        let closed = runner.close_on_daily_profit_target(
+            &mut only_first,
+            cash,
+            initial_capital,
+            &decayed_event,
+        );
+        assert!(closed.is_empty());
+
+        let mut active_trades: HashMap<Uuid, Trade> = [&first, &second]
+            .into_iter()
+            .map(|position| (position.id, runner.open_trade(position, &entry_event, now)))
+            .collect();
+        let cash = initial_capital + first.entry_premium + second.entry_premium;
+        let closed = runner.close_on_daily_profit_target(
+            &mut active_trades,
+            cash,
+            initial_capital,
+            &decayed_event,
+        );
+
+        assert_eq!(closed.len(), 2);
+        assert!(active_trades.is_empty());
+        assert!(runner.strategy.state.active_positions.is_empty());
+        for (trade, _) in &closed {
+            assert_eq!(trade.exit_reason, Some(ExitReason::PortfolioProfitTarget));
+            assert_eq!(trade.metadata["reason"], "portfolio profit target");
+            // Closed at the same quotes the target was checked against
+            let realized = trade.entry_price - trade.exit_price;
+            assert!((realized - position_profit(&first)).abs() < 1e-9);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_am_settled_position_settles_at_the_open() {
+        // The January 2025 monthly expires at the 09:30 EST open on the 17th
+        let expiration = Utc.with_ymd_and_hms(2025, 1, 17, 14, 30, 0).unwrap();
+        let events = vec![
+            create_test_event(400.0, expiration - Duration::days(30)),
+            // Gaps up at the open, then fades back between the shorts by the close
+            create_test_event(425.0, expiration),
+            create_test_event(
+                405.0,
+                expiration + Duration::hours(6) + Duration::minutes(30),
+            ),
+        ];
+        let run = |assignment_fee| {
+            let config = BacktestConfig {
+                commission: CommissionModel {
+                    assignment_fee,
+                    ..Default::default()
+                },
+                product: ProductConfig::am_settled(),
+                ..Default::default()
+            };
+            let mut runner = create_test_runner(config, events.clone());
+            async move {
+                let metrics = runner.run().await;
+                (runner, metrics)
+            }
+        };
+
+        let (runner, free) = run(0.0).await;
+        let trade = &runner.trades[0];
+        assert_eq!(trade.exit_reason, Some(ExitReason::Expiration));
+        assert_eq!(trade.exit_time, expiration);
+        assert_eq!(trade.metadata["exit_underlying_price"], 425.0);
+        // The 420 short call settles 5 in the money against the open
+        assert!((trade.exit_price - 5.0).abs() < 1e-9);
+
+        // Cash settlement involves no assignment, so no assignment fee
+        let (_, charged) = run(7.5).await;
+        assert_eq!(free.final_capital, charged.final_capital);
+    }
+
+    #[tokio::test]
+    async fn test_multi_contract_pnl_matches_capital_change() {
+        let now = Utc::now();
+        // Expires with the 420 short call 5 in the money on each of the 3 contracts
+        let events = vec![
+            create_test_event(400.0, now),
+            create_test_event(425.0, now + Duration::days(30) + Duration::hours(1)),
+        ];
+        let strategy = IronCondorSignalGenerator::new(IronCondorConfig {
+            quantity: 3,
+            ..Default::default()
+        });
+        let config = BacktestConfig {
+            commission: CommissionModel {
+                per_contract: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut runner = BacktestRunner::new(
+            config,
+            HistoricalMarketGenerator::new("SPY".to_string(), events),
+            strategy,
+        );
+        let metrics = runner.run().await;
+
+        let trade = &runner.trades[0];
+        assert_eq!(trade.quantity, 3);
+        assert!((trade.exit_price - 15.0).abs() < 1e-9);
+        assert_eq!(metrics.losing_trades, 1);
+
+        // Metrics report dollars, the runner's capital per-share premium
+        let capital_change = (metrics.final_capital - metrics.initial_capital) * 100.0;
+        let net_profit = metrics.avg_profit_per_win * metrics.winning_trades as f64
+            - metrics.avg_loss_per_loss * metrics.losing_trades as f64;
+        assert!((net_profit - capital_change).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_close_open_at_end_pays_the_chain_quotes() {
+        let now = Utc::now();
+        // Same expiration as the entry, so the last event quotes every leg of the position
+        let mut last_event = create_test_event(421.0, now);
+        last_event.timestamp = now + Duration::hours(6);
+        let chain = last_event.options_chains.values().next().unwrap().clone();
+        let events = vec![create_test_event(400.0, now), last_event];
+        let config = BacktestConfig {
+            close_open_at_end: true,
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, events);
+        runner.run().await;
+
+        let trade = &runner.trades[0];
+        assert_eq!(trade.exit_reason, Some(ExitReason::EndOfBacktest));
+        let quoted_cost = chain.get_call(420.0).unwrap().ask - chain.get_call(430.0).unwrap().bid
+            + chain.get_put(380.0).unwrap().ask
+            - chain.get_put(370.0).unwrap().bid;
+        assert!((trade.exit_price - quoted_cost).abs() < 1e-9);
+        // The time value left in the legs puts the close above the 1.00 intrinsic
+        assert!(trade.exit_price > 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_margin_check_uses_the_chain_quotes() {
+        let now = Utc::now();
+        let entry_event = create_test_event(400.0, now);
+        // Still out of the money, but 30 days out the 420/430 call spread is worth a lot
+        let mut rally_event = create_test_event(418.0, now);
+        rally_event.timestamp = now + Duration::hours(1);
+        let quote = |event: &EnhancedMarketEvent, option_type, strike| {
+            let chain = event.options_chains.values().next().unwrap();
+            match option_type {
+                OptionType::Call => chain.get_call(strike).unwrap().clone(),
+                OptionType::Put => chain.get_put(strike).unwrap().clone(),
+            }
+        };
+        let credit = quote(&entry_event, OptionType::Call, 420.0).bid
+            - quote(&entry_event, OptionType::Call, 430.0).ask
+            + quote(&entry_event, OptionType::Put, 380.0).bid
+            - quote(&entry_event, OptionType::Put, 370.0).ask;
+        let close_cost = quote(&rally_event, OptionType::Call, 420.0).ask
+            - quote(&rally_event, OptionType::Call, 430.0).bid
+            + quote(&rally_event, OptionType::Put, 380.0).ask
+            - quote(&rally_event, OptionType::Put, 370.0).bid;
+
+        // Entering leaves cash of the 10 margin plus half the quoted close cost
+        let config = BacktestConfig {
+            initial_capital: 10.0 - credit + close_cost / 2.0,
+            commission: CommissionModel {
+                per_contract: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut runner = create_test_runner(config, vec![entry_event, rally_event]);
+        let metrics = runner.run().await;
+
+        assert_eq!(metrics.margin_calls, 1);
+        let trade = &runner.trades[0];
+        assert_eq!(trade.exit_reason, Some(ExitReason::MarginCall));
+        assert!((trade.exit_price - close_cost).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_realized_vol_is_annualized_by_the_event_spacing() {
+        let start = Utc::now();
+        // Hourly events: the holding period's returns annualize over 365 * 24 hours
+        let prices = [400.0, 402.0, 399.0, 401.0, 398.0, 400.0];
+        let events: Vec<EnhancedMarketEvent> = prices
+            .iter()
+            .enumerate()
+            .map(|(hour, price)| create_test_event(*price, start + Duration::hours(hour as i64)))
+            .collect();
+        let strategy = IronCondorSignalGenerator::new(IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(2.0),
+            ..Default::default()
+        });
+        let mut runner = BacktestRunner::new(
+            BacktestConfig::default(),
+            HistoricalMarketGenerator::new("SPY".to_string(), events),
+            strategy,
+        );
+        runner.run().await;
+
+        let expected = math::realized_volatility(&prices, 365.0 * 24.0).unwrap();
+        let realized_vol = runner.trades[0].realized_vol.unwrap();
+        assert!((realized_vol - expected).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+
+/// Source of the current time, so time-dependent code can run against a fixed instant
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Always reports the same instant, for reproducible tests
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
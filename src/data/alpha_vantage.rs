@@ -1,12 +1,18 @@
 use chrono::{DateTime, Utc};
+use futures::future::LocalBoxFuture;
+use futures::stream::{self, StreamExt};
 use reqwest;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::error::Error;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::backtest::runner::EnhancedMarketEvent;
+use crate::clock::{Clock, SystemClock};
+use crate::data::provider::{FetchResult, OptionsDataProvider};
 use crate::models::options_data::{Greeks, OptionType, OptionsChain, OptionsContract};
+use crate::models::pricing::{DEFAULT_RISK_FREE_RATE, implied_volatility_from_price};
+use crate::models::product::ProductConfig;
 
 /// Alpha Vantage API response structures
 #[derive(Debug, Deserialize)]
@@ -17,6 +23,44 @@ struct AlphaVantageResponse {
     error_message: Option<String>,
     #[serde(rename = "Note")]
     note: Option<String>,
+    /// Newer responses report rate limiting here instead of in `Note`
+    #[serde(rename = "Information")]
+    information: Option<String>,
+}
+
+impl AlphaVantageResponse {
+    /// Options data of a successful response, or the API error it reports
+    fn into_options(self) -> Result<Vec<AlphaVantageOption>, Box<dyn Error>> {
+        if let Some(error) = self.error_message {
+            return Err(format!("Alpha Vantage API error: {}", error).into());
+        }
+
+        let is_rate_limit = |message: &str| {
+            let message = message.to_lowercase();
+            message.contains("api call frequency") || message.contains("rate limit")
+        };
+        if [&self.note, &self.information]
+            .into_iter()
+            .flatten()
+            .any(|message| is_rate_limit(message))
+        {
+            return Err("Alpha Vantage API rate limit exceeded".into());
+        }
+
+        let Some(options_data) = self.data else {
+            // Any other Information message, e.g. an invalid API key, explains the missing data
+            return Err(match self.information {
+                Some(information) => format!("Alpha Vantage API error: {}", information).into(),
+                None => "No options data returned from Alpha Vantage".into(),
+            });
+        };
+
+        if options_data.is_empty() {
+            return Err("Empty options data from Alpha Vantage".into());
+        }
+
+        Ok(options_data)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,6 +109,12 @@ pub struct AlphaVantageClient {
     client: reqwest::Client,
     api_key: String,
     base_url: String,
+    /// Maximum number of requests in flight in `fetch_many`
+    max_concurrency: usize,
+    /// Per-symbol product specs; symbols without an entry use `ProductConfig::default()`
+    products: HashMap<String, ProductConfig>,
+    /// Time the converted events are stamped with and DTEs are counted from
+    clock: Box<dyn Clock>,
 }
 
 impl AlphaVantageClient {
@@ -75,9 +125,56 @@ impl AlphaVantageClient {
             client: reqwest::Client::new(),
             api_key,
             base_url: "https://www.alphavantage.co".to_string(),
+            max_concurrency: 5,
+            products: HashMap::new(),
+            clock: Box::new(SystemClock),
         }
     }
 
+    /// Read the current time from `clock` instead of the system clock
+    #[allow(dead_code)]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Register the product spec (e.g. expiration time) for a symbol
+    #[allow(dead_code)]
+    pub fn with_product(mut self, symbol: impl Into<String>, product: ProductConfig) -> Self {
+        self.products.insert(symbol.into(), product);
+        self
+    }
+
+    /// Point the client at a different API host (e.g. a proxy or mock server)
+    #[allow(dead_code)]
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Limit how many requests `fetch_many` keeps in flight to respect rate limits
+    #[allow(dead_code)]
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Fetch options chains for several symbols concurrently.
+    /// Results are returned in the same order as `symbols`.
+    pub async fn fetch_many(
+        &self,
+        symbols: &[&str],
+    ) -> Vec<Result<EnhancedMarketEvent, Box<dyn Error>>> {
+        let mut results: Vec<_> = stream::iter(symbols.iter().enumerate())
+            .map(|(idx, symbol)| async move { (idx, self.fetch_options_chain(symbol).await) })
+            .buffer_unordered(self.max_concurrency)
+            .collect()
+            .await;
+
+        results.sort_by_key(|(idx, _)| *idx);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
     /// Fetch current options chain for a symbol using HISTORICAL_OPTIONS endpoint
     /// This is free and includes recent data
     pub async fn fetch_options_chain(
@@ -104,24 +201,7 @@ impl AlphaVantageClient {
         }
 
         let alpha_data: AlphaVantageResponse = response.json().await?;
-
-        if let Some(error) = alpha_data.error_message {
-            return Err(format!("Alpha Vantage API error: {}", error).into());
-        }
-
-        if let Some(note) = alpha_data.note {
-            if note.contains("API call frequency") {
-                return Err("Alpha Vantage API rate limit exceeded".into());
-            }
-        }
-
-        let options_data = alpha_data
-            .data
-            .ok_or("No options data returned from Alpha Vantage")?;
-
-        if options_data.is_empty() {
-            return Err("Empty options data from Alpha Vantage".into());
-        }
+        let options_data = alpha_data.into_options()?;
 
         let event = self.convert_to_enhanced_market_event(&options_data, symbol)?;
 
@@ -144,7 +224,7 @@ impl AlphaVantageClient {
         options_data: &[AlphaVantageOption],
         symbol: &str,
     ) -> Result<EnhancedMarketEvent, Box<dyn Error>> {
-        let current_time = Utc::now();
+        let current_time = self.clock.now();
 
         // Calculate a reasonable underlying price from the options data
         // Use the average of strike prices as a rough estimate
@@ -158,6 +238,10 @@ impl AlphaVantageClient {
         } else {
             strikes.iter().sum::<f64>() / strikes.len() as f64
         };
+        warn!(
+            "No underlying quote for {}, estimating price as ${:.2} from strikes",
+            symbol, underlying_price
+        );
 
         // Calculate average implied volatility
         let valid_ivs: Vec<f64> = options_data
@@ -176,6 +260,8 @@ impl AlphaVantageClient {
             .filter_map(|opt| opt.volume.as_ref()?.parse::<f64>().ok())
             .sum::<f64>();
 
+        let product = self.products.get(symbol).cloned().unwrap_or_default();
+
         // Group options by expiration
         let mut options_by_expiration: HashMap<String, Vec<&AlphaVantageOption>> = HashMap::new();
 
@@ -191,11 +277,11 @@ impl AlphaVantageClient {
 
         for (exp_key, exp_options) in options_by_expiration {
             // Parse expiration date
-            let expiration = chrono::NaiveDate::parse_from_str(&exp_key, "%Y-%m-%d")
-                .map_err(|_| "Invalid expiration date format")?
-                .and_hms_opt(16, 0, 0)
-                .ok_or("Invalid time")?
-                .and_utc();
+            let expiration_date = chrono::NaiveDate::parse_from_str(&exp_key, "%Y-%m-%d")
+                .map_err(|_| "Invalid expiration date format")?;
+            let expiration = product
+                .expiration_utc(expiration_date)
+                .ok_or("Expiration time does not exist in the product timezone")?;
 
             let mut calls = HashMap::new();
             let mut puts = HashMap::new();
@@ -216,23 +302,71 @@ impl AlphaVantageClient {
                     current_time,
                 )?;
 
+                if !contract.is_valid_market() {
+                    warn!(
+                        "Skipping {} {:?} {}: invalid market bid={:.2} ask={:.2}",
+                        option.contract_id,
+                        option_type,
+                        contract.strike,
+                        contract.bid,
+                        contract.ask
+                    );
+                    continue;
+                }
+
                 let strike = option
                     .strike
                     .parse::<f64>()
                     .map_err(|_| "Invalid strike price")?;
-                let strike_key = strike as u32;
+                // Exact strikes, so e.g. 400.0 and 400.5 are never taken for duplicates
+                let strike_key = strike.to_bits();
 
-                match option_type {
-                    OptionType::Call => calls.insert(strike_key, contract),
-                    OptionType::Put => puts.insert(strike_key, contract),
+                let contracts = match option_type {
+                    OptionType::Call => &mut calls,
+                    OptionType::Put => &mut puts,
                 };
+                let date = option.date.as_deref();
+                match contracts.get(&strike_key) {
+                    Some((existing, existing_date)) => {
+                        let replace = contract_quality(&contract, date)
+                            > contract_quality(existing, *existing_date);
+                        warn!(
+                            "Duplicate {:?} {} for {} expiring {}; keeping {}",
+                            option_type,
+                            strike,
+                            symbol,
+                            exp_key,
+                            if replace {
+                                &option.contract_id
+                            } else {
+                                "the earlier row"
+                            }
+                        );
+                        if replace {
+                            contracts.insert(strike_key, (contract, date));
+                        }
+                    }
+                    None => {
+                        contracts.insert(strike_key, (contract, date));
+                    }
+                }
             }
 
+            let calls: HashMap<u32, OptionsContract> = calls
+                .into_values()
+                .map(|(contract, _)| (OptionsChain::strike_key(contract.strike), contract))
+                .collect();
+            let puts: HashMap<u32, OptionsContract> = puts
+                .into_values()
+                .map(|(contract, _)| (OptionsChain::strike_key(contract.strike), contract))
+                .collect();
+
             if !calls.is_empty() || !puts.is_empty() {
                 let chain = OptionsChain {
                     underlying: symbol.to_string(),
                     expiration,
                     underlying_price,
+                    underlying_price_estimated: true,
                     calls,
                     puts,
                     timestamp: current_time,
@@ -249,6 +383,8 @@ impl AlphaVantageClient {
         Ok(EnhancedMarketEvent {
             symbol: symbol.to_string(),
             underlying_price,
+            // The response carries no underlying quote, so the price above is always a guess
+            underlying_price_estimated: true,
             volume: total_volume,
             implied_volatility: avg_iv,
             options_chains,
@@ -262,7 +398,7 @@ impl AlphaVantageClient {
         alpha_option: &AlphaVantageOption,
         option_type: OptionType,
         underlying: &str,
-        _underlying_price: f64,
+        underlying_price: f64,
         expiration: DateTime<Utc>,
         current_time: DateTime<Utc>,
     ) -> Result<OptionsContract, Box<dyn Error>> {
@@ -272,17 +408,20 @@ impl AlphaVantageClient {
             .parse::<f64>()
             .map_err(|_| "Invalid strike price")?;
 
-        let bid = alpha_option
+        let quoted_bid = alpha_option
             .bid
             .as_ref()
-            .and_then(|b| b.parse::<f64>().ok())
-            .unwrap_or(0.01);
-
-        let ask = alpha_option
+            .and_then(|b| b.parse::<f64>().ok());
+        let quoted_ask = alpha_option
             .ask
             .as_ref()
-            .and_then(|a| a.parse::<f64>().ok())
-            .unwrap_or(bid + 0.01);
+            .and_then(|a| a.parse::<f64>().ok());
+        let two_sided_quote = matches!(
+            (quoted_bid, quoted_ask),
+            (Some(bid), Some(ask)) if bid > 0.0 && ask > 0.0
+        );
+        let bid = quoted_bid.unwrap_or(0.01);
+        let ask = quoted_ask.unwrap_or(bid + 0.01);
 
         let last_price = alpha_option
             .last
@@ -291,8 +430,7 @@ impl AlphaVantageClient {
             .or(alpha_option
                 .mark
                 .as_ref()
-                .and_then(|m| m.parse::<f64>().ok()))
-            .unwrap_or((bid + ask) / 2.0);
+                .and_then(|m| m.parse::<f64>().ok()));
 
         let volume = alpha_option
             .volume
@@ -306,11 +444,10 @@ impl AlphaVantageClient {
             .and_then(|oi| oi.parse::<u32>().ok())
             .unwrap_or(0);
 
-        let implied_volatility = alpha_option
+        let feed_iv = alpha_option
             .implied_volatility
             .as_ref()
-            .and_then(|iv| iv.parse::<f64>().ok())
-            .unwrap_or(0.20);
+            .and_then(|iv| iv.parse::<f64>().ok());
 
         // Parse Greeks if available
         let greeks = Greeks {
@@ -341,27 +478,77 @@ impl AlphaVantageClient {
                 .unwrap_or(0.0),
         };
 
-        Ok(OptionsContract {
+        let mut contract = OptionsContract {
             underlying: underlying.to_string(),
             option_type,
             strike,
             expiration,
             bid,
             ask,
-            last_price,
-            implied_volatility,
+            last_price: last_price.unwrap_or_default(),
+            implied_volatility: feed_iv.unwrap_or(0.20),
             open_interest,
             volume,
             greeks,
             dte: (expiration - current_time).num_days().max(0) as u32,
             timestamp: current_time,
-        })
+        };
+        // Without a trade or mark, fall back to the quote midpoint
+        if last_price.is_none() {
+            contract.last_price = contract.mid();
+        }
+        // No IV in the feed: back it out of the quote midpoint, which a one-sided or
+        // empty quote doesn't give
+        if feed_iv.is_none() && two_sided_quote {
+            let days = (expiration - current_time).num_seconds() as f64 / 86_400.0;
+            if let Some(implied_volatility) = implied_volatility_from_price(
+                contract.mid(),
+                underlying_price,
+                strike,
+                DEFAULT_RISK_FREE_RATE,
+                days,
+                option_type,
+            ) {
+                contract.implied_volatility = implied_volatility;
+            }
+        }
+
+        Ok(contract)
+    }
+}
+
+/// Ranking of duplicate rows: contracts that actually traded or have open interest win,
+/// then the most recent quote date
+fn contract_quality<'a>(
+    contract: &OptionsContract,
+    date: Option<&'a str>,
+) -> (bool, Option<&'a str>) {
+    let has_activity = contract.volume > 0 || contract.open_interest > 0;
+    (has_activity, date)
+}
+
+impl OptionsDataProvider for AlphaVantageClient {
+    fn name(&self) -> &str {
+        "Alpha Vantage"
+    }
+
+    fn fetch_options_chain<'a>(&'a self, symbol: &'a str) -> LocalBoxFuture<'a, FetchResult> {
+        Box::pin(AlphaVantageClient::fetch_options_chain(self, symbol))
+    }
+
+    fn fetch_many<'a>(&'a self, symbols: &'a [&'a str]) -> LocalBoxFuture<'a, Vec<FetchResult>> {
+        Box::pin(AlphaVantageClient::fetch_many(self, symbols))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::FixedClock;
+    use chrono::TimeZone;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
 
     #[tokio::test]
     async fn test_alpha_vantage_demo() {
@@ -392,4 +579,305 @@ mod tests {
             }
         }
     }
+
+    fn sample_options() -> Vec<AlphaVantageOption> {
+        serde_json::from_str(
+            r#"[
+                {"contractID": "SPY250117C00400000", "symbol": "SPY", "expiration": "2025-01-17",
+                 "strike": "400.00", "type": "call", "bid": "5.10", "ask": "5.20"},
+                {"contractID": "SPY250117P00400000", "symbol": "SPY", "expiration": "2025-01-17",
+                 "strike": "400.00", "type": "put", "bid": "4.90", "ask": "5.00"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_am_settled_product_expires_at_open() {
+        let client = AlphaVantageClient::new("demo".to_string())
+            .with_product("SPX", ProductConfig::am_settled());
+
+        let am_event = client
+            .convert_to_enhanced_market_event(&sample_options(), "SPX")
+            .unwrap();
+        let am_chain = am_event.get_options_chain("2025-01-17").unwrap();
+        // 09:30 EST
+        assert_eq!(
+            am_chain.expiration.time(),
+            chrono::NaiveTime::from_hms_opt(14, 30, 0).unwrap()
+        );
+        assert!(
+            am_chain
+                .calls
+                .values()
+                .all(|c| c.expiration == am_chain.expiration)
+        );
+
+        // Unregistered symbols keep the PM close
+        let pm_event = client
+            .convert_to_enhanced_market_event(&sample_options(), "SPY")
+            .unwrap();
+        let pm_chain = pm_event.get_options_chain("2025-01-17").unwrap();
+        // 16:00 EST
+        assert_eq!(
+            pm_chain.expiration.time(),
+            chrono::NaiveTime::from_hms_opt(21, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fixed_clock_makes_dte_deterministic() {
+        // A week before the 16:00 EST expiration
+        let now = Utc.with_ymd_and_hms(2025, 1, 10, 21, 0, 0).unwrap();
+        let client = AlphaVantageClient::new("demo".to_string()).with_clock(FixedClock(now));
+
+        let convert = || {
+            client
+                .convert_to_enhanced_market_event(&sample_options(), "SPY")
+                .unwrap()
+        };
+        let event = convert();
+        assert_eq!(event.timestamp, now);
+        let chain = event.get_options_chain("2025-01-17").unwrap();
+        assert_eq!(chain.timestamp, now);
+        assert!(
+            chain
+                .calls
+                .values()
+                .chain(chain.puts.values())
+                .all(|c| c.dte == 7)
+        );
+
+        let again = convert();
+        assert_eq!(again.timestamp, event.timestamp);
+        assert_eq!(
+            again
+                .get_options_chain("2025-01-17")
+                .unwrap()
+                .get_call(400.0)
+                .unwrap()
+                .dte,
+            7
+        );
+    }
+
+    #[test]
+    fn test_converter_excludes_crossed_markets() {
+        let client = AlphaVantageClient::new("demo".to_string());
+        let mut options = sample_options();
+        options.push(
+            serde_json::from_str(
+                r#"{"contractID": "SPY250117C00405000", "symbol": "SPY", "expiration": "2025-01-17",
+                    "strike": "405.00", "type": "call", "bid": "3.10", "ask": "2.90"}"#,
+            )
+            .unwrap(),
+        );
+
+        let event = client
+            .convert_to_enhanced_market_event(&options, "SPY")
+            .unwrap();
+        let chain = event.get_options_chain("2025-01-17").unwrap();
+
+        assert!(chain.get_call(400.0).is_some());
+        assert!(chain.get_call(405.0).is_none());
+    }
+
+    #[test]
+    fn test_iv_is_only_solved_from_two_sided_quotes() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 10, 21, 0, 0).unwrap();
+        let client = AlphaVantageClient::new("demo".to_string()).with_clock(FixedClock(now));
+        // The wings keep the strike-average price estimate at 400
+        let mut options = sample_options();
+        options.extend(
+            serde_json::from_str::<Vec<AlphaVantageOption>>(
+                r#"[
+                    {"contractID": "SPY250117C00450000", "symbol": "SPY", "expiration": "2025-01-17",
+                     "strike": "450.00", "type": "call", "bid": "0.00", "ask": "0.05"},
+                    {"contractID": "SPY250117P00350000", "symbol": "SPY", "expiration": "2025-01-17",
+                     "strike": "350.00", "type": "put", "bid": "0.00", "ask": "0.05"}
+                ]"#,
+            )
+            .unwrap(),
+        );
+
+        let event = client
+            .convert_to_enhanced_market_event(&options, "SPY")
+            .unwrap();
+        let chain = event.get_options_chain("2025-01-17").unwrap();
+
+        assert_ne!(chain.get_call(400.0).unwrap().implied_volatility, 0.20);
+        // Nothing bid: the one-sided quote has no meaningful mid to solve from
+        assert_eq!(chain.get_call(450.0).unwrap().implied_volatility, 0.20);
+    }
+
+    #[test]
+    fn test_converted_event_flags_estimated_price() {
+        let client = AlphaVantageClient::new("demo".to_string());
+
+        let event = client
+            .convert_to_enhanced_market_event(&sample_options(), "SPY")
+            .unwrap();
+
+        assert!(event.underlying_price_estimated);
+        assert!(
+            event
+                .options_chains
+                .values()
+                .all(|chain| chain.underlying_price_estimated)
+        );
+    }
+
+    #[test]
+    fn test_duplicate_strikes_keep_higher_quality_contract() {
+        let client = AlphaVantageClient::new("demo".to_string());
+        let options: Vec<AlphaVantageOption> = serde_json::from_str(
+            r#"[
+                {"contractID": "SPY250117C00400000-active", "symbol": "SPY", "expiration": "2025-01-17",
+                 "strike": "400.00", "type": "call", "bid": "5.10", "ask": "5.20",
+                 "volume": "120", "open_interest": "900", "date": "2025-01-10"},
+                {"contractID": "SPY250117C00400000-stale", "symbol": "SPY", "expiration": "2025-01-17",
+                 "strike": "400.00", "type": "call", "bid": "1.00", "ask": "9.00",
+                 "volume": "0", "open_interest": "0", "date": "2025-01-10"},
+                {"contractID": "SPY250117P00400000-old", "symbol": "SPY", "expiration": "2025-01-17",
+                 "strike": "400.00", "type": "put", "bid": "4.90", "ask": "5.00",
+                 "volume": "10", "open_interest": "50", "date": "2025-01-09"},
+                {"contractID": "SPY250117P00400000-new", "symbol": "SPY", "expiration": "2025-01-17",
+                 "strike": "400.00", "type": "put", "bid": "4.70", "ask": "4.80",
+                 "volume": "10", "open_interest": "50", "date": "2025-01-10"}
+            ]"#,
+        )
+        .unwrap();
+
+        let event = client
+            .convert_to_enhanced_market_event(&options, "SPY")
+            .unwrap();
+        let chain = event.get_options_chain("2025-01-17").unwrap();
+
+        // Zero volume/OI loses even though it came last
+        let call = chain.get_call(400.0).unwrap();
+        assert_eq!((call.bid, call.ask), (5.10, 5.20));
+        // Equal activity: the more recent quote date wins
+        let put = chain.get_put(400.0).unwrap();
+        assert_eq!((put.bid, put.ask), (4.70, 4.80));
+    }
+
+    #[test]
+    fn test_fractional_strikes_sharing_a_dollar_are_not_duplicates() {
+        let client = AlphaVantageClient::new("demo".to_string());
+        let options: Vec<AlphaVantageOption> = serde_json::from_str(
+            r#"[
+                {"contractID": "SPY250117C00400000", "symbol": "SPY", "expiration": "2025-01-17",
+                 "strike": "400.00", "type": "call", "bid": "5.10", "ask": "5.20",
+                 "volume": "120", "open_interest": "900", "date": "2025-01-10"},
+                {"contractID": "SPY250117C00400500", "symbol": "SPY", "expiration": "2025-01-17",
+                 "strike": "400.50", "type": "call", "bid": "4.80", "ask": "4.90",
+                 "volume": "80", "open_interest": "600", "date": "2025-01-10"}
+            ]"#,
+        )
+        .unwrap();
+
+        let event = client
+            .convert_to_enhanced_market_event(&options, "SPY")
+            .unwrap();
+        let chain = event.get_options_chain("2025-01-17").unwrap();
+
+        assert_eq!(chain.calls.len(), 2);
+        let call = chain.get_call(400.0).unwrap();
+        assert_eq!((call.bid, call.ask), (5.10, 5.20));
+        let call = chain.get_call(400.5).unwrap();
+        assert_eq!((call.bid, call.ask), (4.80, 4.90));
+    }
+
+    /// Serve canned HISTORICAL_OPTIONS responses for whatever symbol is requested
+    async fn spawn_mock_server(requested: Arc<Mutex<Vec<String>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    break;
+                };
+                let requested = requested.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let n = socket.read(&mut buf).await.unwrap();
+                    let request = String::from_utf8_lossy(&buf[..n]);
+                    let symbol = request
+                        .split("symbol=")
+                        .nth(1)
+                        .and_then(|rest| rest.split('&').next())
+                        .unwrap_or_default()
+                        .to_string();
+                    requested.lock().unwrap().push(symbol.clone());
+
+                    let body = format!(
+                        r#"{{"data": [{{"contractID": "{symbol}C400", "symbol": "{symbol}",
+                        "expiration": "2025-01-17", "strike": "400.00", "type": "call",
+                        "bid": "5.10", "ask": "5.20"}}]}}"#
+                    );
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn test_information_field_is_a_rate_limit() {
+        let response: AlphaVantageResponse = serde_json::from_str(
+            r#"{"Information": "Thank you for using Alpha Vantage! Our standard API rate limit is 25 requests per day."}"#,
+        )
+        .unwrap();
+
+        let error = response.into_options().unwrap_err();
+        assert_eq!(error.to_string(), "Alpha Vantage API rate limit exceeded");
+
+        // The legacy Note field is still recognized
+        let response: AlphaVantageResponse = serde_json::from_str(
+            r#"{"Note": "Thank you for using Alpha Vantage! Our standard API call frequency is 5 calls per minute."}"#,
+        )
+        .unwrap();
+        assert!(response.into_options().is_err());
+
+        // Other Information messages are reported as they are
+        let response: AlphaVantageResponse = serde_json::from_str(
+            r#"{"Information": "the parameter apikey is invalid or missing."}"#,
+        )
+        .unwrap();
+        let error = response.into_options().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Alpha Vantage API error: the parameter apikey is invalid or missing."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_many_fetches_all_symbols() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let base_url = spawn_mock_server(requested.clone()).await;
+
+        let client = AlphaVantageClient::new("test".to_string())
+            .with_base_url(base_url)
+            .with_max_concurrency(2);
+
+        let symbols = ["SPY", "QQQ", "IWM", "DIA"];
+        let results = client.fetch_many(&symbols).await;
+
+        assert_eq!(results.len(), symbols.len());
+        for (symbol, result) in symbols.iter().zip(&results) {
+            let event = result.as_ref().unwrap();
+            assert_eq!(&event.symbol, symbol);
+        }
+
+        let mut requested = requested.lock().unwrap().clone();
+        requested.sort();
+        assert_eq!(requested, vec!["DIA", "IWM", "QQQ", "SPY"]);
+    }
 }
@@ -0,0 +1,301 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::Path;
+
+use crate::backtest::runner::EnhancedMarketEvent;
+use crate::models::options_data::{Greeks, OptionType, OptionsChain, OptionsContract};
+use crate::models::product::ProductConfig;
+
+/// Columns every file must provide, as (canonical name, accepted aliases)
+const REQUIRED_COLUMNS: [(&str, &[&str]); 8] = [
+    ("timestamp", &["date", "quote_date"]),
+    ("symbol", &["underlying", "ticker"]),
+    ("underlying_price", &["spot"]),
+    ("expiration", &["expiry"]),
+    ("strike", &[]),
+    ("type", &["option_type"]),
+    ("bid", &[]),
+    ("ask", &[]),
+];
+
+/// Columns that are filled with defaults when absent
+const OPTIONAL_COLUMNS: [(&str, &[&str]); 8] = [
+    ("last", &["last_price"]),
+    ("implied_volatility", &["iv"]),
+    ("open_interest", &["oi"]),
+    ("volume", &[]),
+    ("delta", &[]),
+    ("gamma", &[]),
+    ("theta", &[]),
+    ("vega", &[]),
+];
+
+/// Header name -> column index, resolved from the file's header row so columns can come
+/// in any order and under common aliases
+#[derive(Debug, Clone)]
+pub struct CsvColumns {
+    indices: HashMap<&'static str, usize>,
+}
+
+impl CsvColumns {
+    /// Map the header row. Errors with the full list of required columns it lacks.
+    pub fn from_header(header: &str) -> Result<Self, Box<dyn Error>> {
+        let names: Vec<String> = header
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .collect();
+        let position = |canonical: &str, aliases: &[&str]| {
+            names
+                .iter()
+                .position(|name| name == canonical || aliases.contains(&name.as_str()))
+        };
+
+        let mut indices = HashMap::new();
+        let mut missing = Vec::new();
+        for (canonical, aliases) in REQUIRED_COLUMNS {
+            match position(canonical, aliases) {
+                Some(index) => {
+                    indices.insert(canonical, index);
+                }
+                None => missing.push(canonical),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(format!("CSV is missing required columns: {}", missing.join(", ")).into());
+        }
+
+        for (canonical, aliases) in OPTIONAL_COLUMNS {
+            if let Some(index) = position(canonical, aliases) {
+                indices.insert(canonical, index);
+            }
+        }
+
+        Ok(Self { indices })
+    }
+
+    /// Raw value of `column` in `fields`, None if the column is absent or the cell empty
+    fn get<'a>(&self, fields: &[&'a str], column: &str) -> Option<&'a str> {
+        let value = fields.get(*self.indices.get(column)?)?.trim();
+        (!value.is_empty()).then_some(value)
+    }
+
+    fn required<'a>(&self, fields: &[&'a str], column: &str) -> Result<&'a str, Box<dyn Error>> {
+        self.get(fields, column)
+            .ok_or_else(|| format!("empty {} value", column).into())
+    }
+
+    fn parse_required<T: std::str::FromStr>(
+        &self,
+        fields: &[&str],
+        column: &str,
+    ) -> Result<T, Box<dyn Error>> {
+        let value = self.required(fields, column)?;
+        value
+            .parse()
+            .map_err(|_| format!("invalid {} value '{}'", column, value).into())
+    }
+
+    fn parse_optional<T: std::str::FromStr>(&self, fields: &[&str], column: &str) -> Option<T> {
+        self.get(fields, column)?.parse().ok()
+    }
+}
+
+/// Load market events from a CSV file of option quotes (one contract per row).
+#[allow(dead_code)]
+pub fn load_csv_events(path: impl AsRef<Path>) -> Result<Vec<EnhancedMarketEvent>, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    parse_csv_events(&contents)
+}
+
+/// Parse CSV option quotes into one event per (timestamp, symbol), ordered by time.
+/// Fields are split on commas; quoted fields are not supported.
+pub fn parse_csv_events(contents: &str) -> Result<Vec<EnhancedMarketEvent>, Box<dyn Error>> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let header = lines.next().ok_or("CSV is empty")?;
+    let columns = CsvColumns::from_header(header)?;
+    let product = ProductConfig::default();
+
+    let mut events: BTreeMap<(DateTime<Utc>, String), EnhancedMarketEvent> = BTreeMap::new();
+    for (line_number, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split(',').collect();
+        // Line numbers are 1-based and count the header
+        add_row(&columns, &fields, &product, &mut events)
+            .map_err(|e| format!("CSV line {}: {}", line_number + 2, e))?;
+    }
+
+    let mut events: Vec<EnhancedMarketEvent> = events.into_values().collect();
+    for event in &mut events {
+        let ivs: Vec<f64> = event
+            .options_chains
+            .values()
+            .flat_map(|chain| chain.calls.values().chain(chain.puts.values()))
+            .map(|contract| contract.implied_volatility)
+            .filter(|iv| *iv > 0.0)
+            .collect();
+        if !ivs.is_empty() {
+            event.implied_volatility = ivs.iter().sum::<f64>() / ivs.len() as f64;
+        }
+    }
+    Ok(events)
+}
+
+fn add_row(
+    columns: &CsvColumns,
+    fields: &[&str],
+    product: &ProductConfig,
+    events: &mut BTreeMap<(DateTime<Utc>, String), EnhancedMarketEvent>,
+) -> Result<(), Box<dyn Error>> {
+    let timestamp = parse_timestamp(columns.required(fields, "timestamp")?)?;
+    let symbol = columns.required(fields, "symbol")?.to_string();
+    let underlying_price: f64 = columns.parse_required(fields, "underlying_price")?;
+
+    let expiration_key = columns.required(fields, "expiration")?;
+    let expiration_date = NaiveDate::parse_from_str(expiration_key, "%Y-%m-%d")
+        .map_err(|_| format!("invalid expiration value '{}'", expiration_key))?;
+    let expiration = product
+        .expiration_utc(expiration_date)
+        .ok_or("Expiration time does not exist in the product timezone")?;
+
+    let option_type = match columns.required(fields, "type")?.to_lowercase().as_str() {
+        "call" | "c" => OptionType::Call,
+        "put" | "p" => OptionType::Put,
+        other => return Err(format!("invalid type value '{}'", other).into()),
+    };
+    let strike: f64 = columns.parse_required(fields, "strike")?;
+    let bid: f64 = columns.parse_required(fields, "bid")?;
+    let ask: f64 = columns.parse_required(fields, "ask")?;
+    let volume = columns.parse_optional(fields, "volume").unwrap_or(0);
+
+    let mut contract = OptionsContract {
+        underlying: symbol.clone(),
+        option_type,
+        strike,
+        expiration,
+        bid,
+        ask,
+        last_price: 0.0,
+        implied_volatility: columns
+            .parse_optional(fields, "implied_volatility")
+            .unwrap_or(0.0),
+        open_interest: columns.parse_optional(fields, "open_interest").unwrap_or(0),
+        volume,
+        greeks: Greeks {
+            delta: columns.parse_optional(fields, "delta").unwrap_or(0.0),
+            gamma: columns.parse_optional(fields, "gamma").unwrap_or(0.0),
+            theta: columns.parse_optional(fields, "theta").unwrap_or(0.0),
+            vega: columns.parse_optional(fields, "vega").unwrap_or(0.0),
+            rho: 0.0,
+        },
+        dte: (expiration - timestamp).num_days().max(0) as u32,
+        timestamp,
+    };
+    // Without a trade, fall back to the quote midpoint
+    contract.last_price = columns
+        .parse_optional(fields, "last")
+        .unwrap_or(contract.mid());
+
+    let event = events
+        .entry((timestamp, symbol.clone()))
+        .or_insert_with(|| EnhancedMarketEvent {
+            symbol: symbol.clone(),
+            underlying_price,
+            underlying_price_estimated: false,
+            volume: 0.0,
+            implied_volatility: 0.20,
+            options_chains: HashMap::new(),
+            timestamp,
+        });
+    event.volume += volume as f64;
+
+    let chain = event
+        .options_chains
+        .entry(expiration_key.to_string())
+        .or_insert_with(|| OptionsChain {
+            underlying: symbol,
+            expiration,
+            underlying_price,
+            underlying_price_estimated: false,
+            calls: HashMap::new(),
+            puts: HashMap::new(),
+            timestamp,
+        });
+    match option_type {
+        OptionType::Call => chain
+            .calls
+            .insert(OptionsChain::strike_key(strike), contract),
+        OptionType::Put => chain
+            .puts
+            .insert(OptionsChain::strike_key(strike), contract),
+    };
+
+    Ok(())
+}
+
+/// RFC 3339 timestamps, or plain dates taken as midnight UTC
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, Box<dyn Error>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+        .ok_or_else(|| format!("invalid timestamp value '{}'", value).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reordered_headers_are_mapped_by_name() {
+        let csv = "\
+ask,bid,strike,type,expiration,underlying_price,symbol,timestamp
+1.30,1.20,405,call,2025-01-17,400.5,SPY,2025-01-10
+1.10,1.00,395,put,2025-01-17,400.5,SPY,2025-01-10
+1.60,1.50,405,call,2025-01-17,402.0,SPY,2025-01-11
+";
+        let events = parse_csv_events(csv).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].underlying_price, 400.5);
+        assert_eq!(events[1].underlying_price, 402.0);
+
+        let chain = events[0].get_options_chain("2025-01-17").unwrap();
+        let call = chain.get_call(405.0).unwrap();
+        assert_eq!((call.bid, call.ask), (1.20, 1.30));
+        let put = chain.get_put(395.0).unwrap();
+        assert_eq!((put.bid, put.ask), (1.00, 1.10));
+        assert_eq!(put.dte, 7);
+    }
+
+    #[test]
+    fn test_alias_columns() {
+        let csv = "\
+timestamp,symbol,underlying_price,expiration,strike,type,bid,ask,iv,oi
+2025-01-10T15:00:00Z,SPY,400.0,2025-01-17,400,call,2.00,2.10,0.18,1500
+";
+        let events = parse_csv_events(csv).unwrap();
+
+        let call = events[0]
+            .get_options_chain("2025-01-17")
+            .unwrap()
+            .get_call(400.0)
+            .unwrap();
+        assert_eq!(call.implied_volatility, 0.18);
+        assert_eq!(call.open_interest, 1500);
+        assert_eq!(events[0].implied_volatility, 0.18);
+    }
+
+    #[test]
+    fn test_missing_required_columns_are_listed() {
+        let csv = "timestamp,symbol,strike,type,ask\n";
+        let error = parse_csv_events(csv).unwrap_err().to_string();
+
+        assert_eq!(
+            error,
+            "CSV is missing required columns: underlying_price, expiration, bid"
+        );
+    }
+}
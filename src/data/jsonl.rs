@@ -0,0 +1,89 @@
+use std::error::Error;
+use std::path::Path;
+use tracing::warn;
+
+use crate::backtest::runner::EnhancedMarketEvent;
+
+/// Load an append-only `.jsonl` log with one serialized `EnhancedMarketEvent` per line,
+/// sorted by timestamp. Malformed lines are skipped with a warning, or abort the load
+/// when `strict` is set.
+#[allow(dead_code)]
+pub fn load_events_from_jsonl(
+    path: impl AsRef<Path>,
+    strict: bool,
+) -> Result<Vec<EnhancedMarketEvent>, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    parse_jsonl_events(&contents, strict)
+}
+
+/// Parse JSON-lines contents; see `load_events_from_jsonl`
+pub fn parse_jsonl_events(
+    contents: &str,
+    strict: bool,
+) -> Result<Vec<EnhancedMarketEvent>, Box<dyn Error>> {
+    let mut events = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<EnhancedMarketEvent>(line) {
+            Ok(event) => events.push(event),
+            Err(e) if strict => {
+                return Err(format!("Malformed event on line {}: {}", index + 1, e).into());
+            }
+            Err(e) => warn!("Skipping malformed event on line {}: {}", index + 1, e),
+        }
+    }
+
+    // Logs may be appended out of order by concurrent writers
+    events.sort_by_key(|event| event.timestamp);
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use std::collections::HashMap;
+
+    fn write_fixture() -> std::path::PathBuf {
+        let start = Utc::now();
+        let event = |hours: i64, underlying_price: f64| EnhancedMarketEvent {
+            symbol: "SPY".to_string(),
+            underlying_price,
+            underlying_price_estimated: false,
+            volume: 0.0,
+            implied_volatility: 0.20,
+            options_chains: HashMap::new(),
+            timestamp: start + Duration::hours(hours),
+        };
+
+        let lines = [
+            serde_json::to_string(&event(2, 402.0)).unwrap(),
+            "{\"symbol\": \"SPY\", \"underlying_price\": ".to_string(),
+            String::new(),
+            serde_json::to_string(&event(1, 401.0)).unwrap(),
+        ];
+        let path = std::env::temp_dir().join(format!("events-{}.jsonl", uuid::Uuid::new_v4()));
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_events_from_jsonl_sorts_and_skips_malformed_lines() {
+        let path = write_fixture();
+
+        let lenient = load_events_from_jsonl(&path, false);
+        let strict = load_events_from_jsonl(&path, true);
+        std::fs::remove_file(&path).unwrap();
+
+        let events = lenient.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].underlying_price, 401.0);
+        assert_eq!(events[1].underlying_price, 402.0);
+
+        let error = strict.unwrap_err().to_string();
+        assert!(error.starts_with("Malformed event on line 2"));
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Keyfile location relative to the home directory
+const KEYFILE_PATH: &str = ".config/iron-condor/keys.toml";
+
+/// `~/.config/iron-condor/keys.toml`, or None when `HOME` isn't set
+pub fn default_keyfile_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(KEYFILE_PATH))
+}
+
+/// API key for `provider`: the `env_var` environment variable when it's set, otherwise
+/// the provider's entry in `keyfile`. A missing keyfile is not an error; a malformed one
+/// is logged and ignored.
+pub fn resolve_api_key(env_var: &str, provider: &str, keyfile: Option<&Path>) -> Option<String> {
+    if let Ok(key) = std::env::var(env_var)
+        && !key.is_empty()
+    {
+        return Some(key);
+    }
+
+    let path = keyfile?;
+    let contents = std::fs::read_to_string(path).ok()?;
+    match parse_keyfile(&contents) {
+        Ok(mut keys) => keys.remove(provider),
+        Err(e) => {
+            warn!("Ignoring keyfile {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Parse `provider = "key"` lines. Blank lines, `#` comments and `[section]` headers are
+/// skipped, so a TOML file with the keys under a table also works.
+pub fn parse_keyfile(contents: &str) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let mut keys = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+
+        let Some((provider, value)) = line.split_once('=') else {
+            return Err(format!("Expected `provider = \"key\"` on line {}", index + 1).into());
+        };
+        let value = value.trim();
+        let key = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        keys.insert(provider.trim().to_string(), key.to_string());
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_is_loaded_from_keyfile_when_env_var_is_absent() {
+        let path = std::env::temp_dir().join(format!("keys-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "# provider keys\n[keys]\nalpha_vantage = \"AV123\"\npolygon = \"PG456\"\n",
+        )
+        .unwrap();
+
+        let env_var = "IRON_CONDOR_TEST_KEY_THAT_IS_NEVER_SET";
+        let alpha_vantage = resolve_api_key(env_var, "alpha_vantage", Some(&path));
+        let polygon = resolve_api_key(env_var, "polygon", Some(&path));
+        let missing = resolve_api_key(env_var, "tradier", Some(&path));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(alpha_vantage.as_deref(), Some("AV123"));
+        assert_eq!(polygon.as_deref(), Some("PG456"));
+        assert_eq!(missing, None);
+        assert_eq!(resolve_api_key(env_var, "alpha_vantage", Some(&path)), None);
+    }
+}
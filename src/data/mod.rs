@@ -1 +1,6 @@
 pub mod alpha_vantage;
+pub mod csv;
+pub mod jsonl;
+pub mod keys;
+pub mod provider;
+pub mod synthetic;
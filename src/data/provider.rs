@@ -0,0 +1,205 @@
+use futures::future::LocalBoxFuture;
+use std::error::Error;
+use tracing::{info, warn};
+
+use crate::backtest::runner::EnhancedMarketEvent;
+
+pub type FetchResult = Result<EnhancedMarketEvent, Box<dyn Error>>;
+
+/// Source of options chain snapshots (e.g. Alpha Vantage)
+pub trait OptionsDataProvider {
+    /// Human-readable provider name for logging
+    fn name(&self) -> &str;
+
+    /// Fetch the current options chains for one symbol
+    fn fetch_options_chain<'a>(&'a self, symbol: &'a str) -> LocalBoxFuture<'a, FetchResult>;
+
+    /// Fetch several symbols, returning results in the same order as `symbols`.
+    /// Fetches one at a time unless the provider overrides it.
+    fn fetch_many<'a>(&'a self, symbols: &'a [&'a str]) -> LocalBoxFuture<'a, Vec<FetchResult>> {
+        Box::pin(async move {
+            let mut results = Vec::with_capacity(symbols.len());
+            for symbol in symbols {
+                results.push(self.fetch_options_chain(symbol).await);
+            }
+            results
+        })
+    }
+}
+
+/// Ordered list of providers where each fetch is served by the first one that succeeds
+#[allow(dead_code)]
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn OptionsDataProvider>>,
+}
+
+#[allow(dead_code)]
+impl FallbackProvider {
+    /// Providers are tried in the given order, most preferred first
+    pub fn new(providers: Vec<Box<dyn OptionsDataProvider>>) -> Self {
+        Self { providers }
+    }
+}
+
+impl OptionsDataProvider for FallbackProvider {
+    fn name(&self) -> &str {
+        "fallback"
+    }
+
+    fn fetch_options_chain<'a>(&'a self, symbol: &'a str) -> LocalBoxFuture<'a, FetchResult> {
+        Box::pin(async move {
+            let mut failures = Vec::new();
+            for provider in &self.providers {
+                match provider.fetch_options_chain(symbol).await {
+                    Ok(event) => {
+                        info!("{} options data served by {}", symbol, provider.name());
+                        return Ok(event);
+                    }
+                    Err(e) => {
+                        warn!(
+                            "{} failed for {}, trying next provider: {}",
+                            provider.name(),
+                            symbol,
+                            e
+                        );
+                        failures.push(format!("{}: {}", provider.name(), e));
+                    }
+                }
+            }
+            Err(format!(
+                "Every provider failed for {} ({})",
+                symbol,
+                failures.join("; ")
+            )
+            .into())
+        })
+    }
+}
+
+/// Fetch every ticker, skipping the ones that fail. Errors if nothing could be fetched.
+pub async fn load_events(
+    provider: &dyn OptionsDataProvider,
+    tickers: &[&str],
+) -> Result<Vec<EnhancedMarketEvent>, Box<dyn Error>> {
+    let results = provider.fetch_many(tickers).await;
+    let mut events = Vec::new();
+
+    for (ticker, result) in tickers.iter().zip(results) {
+        match result {
+            Ok(event) => {
+                info!(
+                    "Successfully fetched live {} options data from {}!",
+                    ticker,
+                    provider.name()
+                );
+                info!("Underlying price: ${:.2}", event.underlying_price);
+                info!("Available expirations: {}", event.options_chains.len());
+                info!(
+                    "Total options contracts: {}",
+                    event
+                        .options_chains
+                        .values()
+                        .map(|c| c.calls.len() + c.puts.len())
+                        .sum::<usize>()
+                );
+                events.push(event);
+            }
+            Err(e) => {
+                warn!("Failed to fetch {} from {}: {}", ticker, provider.name(), e);
+            }
+        }
+    }
+
+    if events.is_empty() {
+        return Err(format!(
+            "All {} ticker fetches from {} failed",
+            tickers.len(),
+            provider.name()
+        )
+        .into());
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    /// Provider that serves a bare event for `available` symbols and fails the rest
+    struct MockProvider {
+        available: Vec<&'static str>,
+        underlying_price: f64,
+    }
+
+    impl OptionsDataProvider for MockProvider {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn fetch_options_chain<'a>(&'a self, symbol: &'a str) -> LocalBoxFuture<'a, FetchResult> {
+            Box::pin(async move {
+                if !self.available.contains(&symbol) {
+                    return Err(format!("no data for {}", symbol).into());
+                }
+                Ok(EnhancedMarketEvent {
+                    symbol: symbol.to_string(),
+                    underlying_price: self.underlying_price,
+                    underlying_price_estimated: false,
+                    volume: 0.0,
+                    implied_volatility: 0.20,
+                    options_chains: HashMap::new(),
+                    timestamp: Utc::now(),
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_events_errors_when_all_fetches_fail() {
+        let provider = MockProvider {
+            available: Vec::new(),
+            underlying_price: 400.0,
+        };
+
+        let error = load_events(&provider, &["SPY", "QQQ"]).await.unwrap_err();
+        assert_eq!(error.to_string(), "All 2 ticker fetches from mock failed");
+    }
+
+    #[tokio::test]
+    async fn test_load_events_skips_failed_tickers() {
+        let provider = MockProvider {
+            available: vec!["QQQ"],
+            underlying_price: 400.0,
+        };
+
+        let events = load_events(&provider, &["SPY", "QQQ"]).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].symbol, "QQQ");
+    }
+
+    #[tokio::test]
+    async fn test_fallback_provider_uses_secondary_when_primary_fails() {
+        let provider = FallbackProvider::new(vec![
+            Box::new(MockProvider {
+                available: Vec::new(),
+                underlying_price: 400.0,
+            }),
+            Box::new(MockProvider {
+                available: vec!["SPY"],
+                underlying_price: 401.5,
+            }),
+        ]);
+
+        let event = provider.fetch_options_chain("SPY").await.unwrap();
+        assert_eq!(event.underlying_price, 401.5);
+
+        let error = provider.fetch_options_chain("QQQ").await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Every provider failed for QQQ (mock: no data for QQQ; mock: no data for QQQ)"
+        );
+    }
+}
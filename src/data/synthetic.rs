@@ -0,0 +1,267 @@
+use chrono::{DateTime, Duration, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+use crate::backtest::runner::EnhancedMarketEvent;
+use crate::models::options_data::{
+    OptionType, OptionsChain, OptionsContract, OptionsContractParams,
+};
+
+/// Market regime that shapes the simulated underlying path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum MarketRegime {
+    /// Low volatility, range-bound price action
+    Choppy,
+    /// Steady upward drift
+    TrendingUp,
+    /// Steady downward drift
+    TrendingDown,
+    /// High volatility with frequent jumps in both directions
+    Whipsaw,
+}
+
+/// Path parameters of a regime (annualized)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegimeParams {
+    /// GBM drift
+    pub drift: f64,
+    /// GBM volatility
+    pub volatility: f64,
+    /// Pull of the log price back to its starting level (0 = pure GBM)
+    pub mean_reversion: f64,
+    /// Expected number of jumps per year
+    pub jump_intensity: f64,
+    /// Standard deviation of the log jump size
+    pub jump_volatility: f64,
+}
+
+impl MarketRegime {
+    pub fn params(&self) -> RegimeParams {
+        match self {
+            MarketRegime::Choppy => RegimeParams {
+                drift: 0.0,
+                volatility: 0.10,
+                mean_reversion: 8.0,
+                jump_intensity: 0.0,
+                jump_volatility: 0.0,
+            },
+            MarketRegime::TrendingUp => RegimeParams {
+                drift: 0.40,
+                volatility: 0.12,
+                mean_reversion: 0.0,
+                jump_intensity: 0.0,
+                jump_volatility: 0.0,
+            },
+            MarketRegime::TrendingDown => RegimeParams {
+                drift: -0.40,
+                volatility: 0.12,
+                mean_reversion: 0.0,
+                jump_intensity: 0.0,
+                jump_volatility: 0.0,
+            },
+            MarketRegime::Whipsaw => RegimeParams {
+                drift: 0.0,
+                volatility: 0.45,
+                mean_reversion: 2.0,
+                jump_intensity: 12.0,
+                jump_volatility: 0.04,
+            },
+        }
+    }
+}
+
+/// Seeded generator of daily market events with options chains under a chosen regime.
+/// The same seed always replays the same path.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct RegimeGenerator {
+    symbol: String,
+    regime: MarketRegime,
+    seed: u64,
+    start_price: f64,
+    dte: i64,
+}
+
+#[allow(dead_code)]
+impl RegimeGenerator {
+    pub fn new(symbol: impl Into<String>, regime: MarketRegime, seed: u64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            regime,
+            seed,
+            start_price: 400.0,
+            dte: 7,
+        }
+    }
+
+    /// Underlying price of the first event
+    pub fn with_start_price(mut self, start_price: f64) -> Self {
+        self.start_price = start_price;
+        self
+    }
+
+    /// Days to expiration of the chain listed on every event
+    pub fn with_dte(mut self, dte: i64) -> Self {
+        self.dte = dte.max(0);
+        self
+    }
+
+    /// Simulate `days` daily underlying prices, starting at `start_price`
+    pub fn price_path(&self, days: usize) -> Vec<f64> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let params = self.regime.params();
+        let dt: f64 = 1.0 / 252.0;
+        let anchor = self.start_price.ln();
+
+        let mut log_price = anchor;
+        let mut path = Vec::with_capacity(days);
+        for day in 0..days {
+            if day > 0 {
+                let diffusion = params.volatility * dt.sqrt() * standard_normal(&mut rng);
+                let reversion = params.mean_reversion * (anchor - log_price) * dt;
+                let jump = if rng.random::<f64>() < params.jump_intensity * dt {
+                    params.jump_volatility * standard_normal(&mut rng)
+                } else {
+                    0.0
+                };
+                log_price += (params.drift - 0.5 * params.volatility.powi(2)) * dt
+                    + reversion
+                    + diffusion
+                    + jump;
+            }
+            path.push(log_price.exp());
+        }
+        path
+    }
+
+    /// Generate one event per day starting at `start`
+    pub fn generate(&self, start: DateTime<Utc>, days: usize) -> Vec<EnhancedMarketEvent> {
+        let implied_vol = self.regime.params().volatility;
+
+        self.price_path(days)
+            .into_iter()
+            .enumerate()
+            .map(|(day, underlying_price)| {
+                let timestamp = start + Duration::days(day as i64);
+                let chain = self.create_chain(underlying_price, implied_vol, timestamp);
+                EnhancedMarketEvent {
+                    symbol: self.symbol.clone(),
+                    underlying_price,
+                    underlying_price_estimated: false,
+                    volume: 0.0,
+                    implied_volatility: implied_vol,
+                    options_chains: HashMap::from([(
+                        chain.expiration.format("%Y-%m-%d").to_string(),
+                        chain,
+                    )]),
+                    timestamp,
+                }
+            })
+            .collect()
+    }
+
+    /// $5-increment chain spanning roughly +/-10% around the underlying
+    fn create_chain(
+        &self,
+        underlying_price: f64,
+        implied_vol: f64,
+        timestamp: DateTime<Utc>,
+    ) -> OptionsChain {
+        let expiration = timestamp + Duration::days(self.dte);
+        let lowest = ((underlying_price * 0.90) / 5.0).floor() as u32 * 5;
+        let highest = ((underlying_price * 1.10) / 5.0).ceil() as u32 * 5;
+
+        let mut calls = HashMap::new();
+        let mut puts = HashMap::new();
+        for strike in (lowest..=highest).step_by(5) {
+            for option_type in [OptionType::Call, OptionType::Put] {
+                let contract = OptionsContract::new(OptionsContractParams {
+                    underlying: self.symbol.clone(),
+                    option_type,
+                    strike: strike as f64,
+                    expiration,
+                    underlying_price,
+                    implied_vol,
+                    risk_free_rate: 0.05,
+                    current_time: timestamp,
+                });
+                match option_type {
+                    OptionType::Call => {
+                        calls.insert(OptionsChain::strike_key(strike as f64), contract)
+                    }
+                    OptionType::Put => {
+                        puts.insert(OptionsChain::strike_key(strike as f64), contract)
+                    }
+                };
+            }
+        }
+
+        OptionsChain {
+            underlying: self.symbol.clone(),
+            expiration,
+            underlying_price,
+            underlying_price_estimated: false,
+            calls,
+            puts,
+            timestamp,
+        }
+    }
+}
+
+/// Standard normal draw via Box-Muller
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    let u1 = rng.random::<f64>().max(f64::MIN_POSITIVE);
+    let u2 = rng.random::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fraction of 20-day windows over which the price rose
+    fn up_window_fraction(path: &[f64]) -> f64 {
+        let windows: Vec<bool> = path.windows(21).map(|w| w[20] > w[0]).collect();
+        windows.iter().filter(|&&up| up).count() as f64 / windows.len() as f64
+    }
+
+    #[test]
+    fn test_trending_regime_drifts_steadily() {
+        let path = RegimeGenerator::new("SPY", MarketRegime::TrendingUp, 42).price_path(252);
+
+        assert!(path[251] > path[0] * 1.25);
+        assert!(up_window_fraction(&path) > 0.8);
+
+        let down = RegimeGenerator::new("SPY", MarketRegime::TrendingDown, 42).price_path(252);
+        assert!(down[251] < down[0] * 0.80);
+        assert!(up_window_fraction(&down) < 0.2);
+    }
+
+    #[test]
+    fn test_choppy_regime_has_no_drift() {
+        let path = RegimeGenerator::new("SPY", MarketRegime::Choppy, 42).price_path(252);
+
+        assert!((path[251] / path[0] - 1.0).abs() < 0.05);
+        let up_fraction = up_window_fraction(&path);
+        assert!(up_fraction > 0.2 && up_fraction < 0.8);
+    }
+
+    #[test]
+    fn test_generator_is_replayable() {
+        let generator = RegimeGenerator::new("SPY", MarketRegime::Whipsaw, 7).with_dte(1);
+        let start = Utc::now();
+
+        let first = generator.generate(start, 30);
+        let second = generator.generate(start, 30);
+
+        assert_eq!(first.len(), 30);
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.underlying_price, b.underlying_price);
+            assert_eq!(a.options_chains.len(), 1);
+        }
+        let chain = first[0].options_chains.values().next().unwrap();
+        assert_eq!((chain.expiration - first[0].timestamp).num_days(), 1);
+    }
+}
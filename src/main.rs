@@ -1,13 +1,21 @@
 mod backtest;
+mod clock;
 mod data;
+mod math;
 mod models;
 mod strategies;
+#[cfg(test)]
+mod test_fixtures;
 
-use backtest::runner::{BacktestConfig, BacktestRunner, HistoricalMarketGenerator};
+use backtest::runner::{
+    BacktestConfig, BacktestRunner, CommissionModel, HistoricalMarketGenerator,
+};
 use chrono::{Duration, Utc};
 use data::alpha_vantage::AlphaVantageClient;
+use data::keys;
+use data::provider::load_events;
 use dotenv::dotenv;
-use strategies::iron_condor::{IronCondorConfig, IronCondorSignalGenerator};
+use strategies::iron_condor::{IronCondorConfig, IronCondorSignalGenerator, ProfitTarget};
 use tracing::{Level, info, warn};
 use tracing_subscriber::FmtSubscriber;
 
@@ -34,13 +42,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let iron_condor_config = IronCondorConfig {
         symbol: symbol.clone(),
-        dte_threshold: 1,        // 0DTE
-        width_percentage: 0.01,  // 1% width between strikes
-        delta_target: 0.10,      // 10 delta for short strikes
-        profit_target_pct: 0.50, // 50% profit target
-        stop_loss_pct: 0.75,     // 75% stop loss
-        exit_dte: 0,             // Hold till expiration (0DTE)
-        zero_dte: true,          // 0DTE strategy
+        dte_threshold: 1,                            // 0DTE
+        width_percentage: 0.01,                      // 1% width between strikes
+        delta_target: 0.10,                          // 10 delta for short strikes
+        profit_target: ProfitTarget::PctOfMax(0.50), // 50% profit target
+        stop_loss_pct: 0.75,                         // 75% stop loss
+        exit_dte: 0,                                 // Hold till expiration (0DTE)
+        zero_dte: true,                              // 0DTE strategy
+        ..Default::default()
     };
 
     let signal_generator = IronCondorSignalGenerator::new(iron_condor_config);
@@ -49,8 +58,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         initial_capital: 100_000.0,
         start_date: Utc::now() - Duration::days(365 * 3),
         end_date: Utc::now(),
-        commission_per_contract: 0.65,
+        commission: CommissionModel {
+            per_contract: 0.65,
+            ..Default::default()
+        },
         slippage_pct: 0.03,
+        hold_to_expiry_shadow: true,
+        ..Default::default()
     };
 
     let mut backtest_runner =
@@ -61,16 +75,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Initial Capital: ${:.2}", metrics.initial_capital);
     info!("Final Capital: ${:.2}", metrics.final_capital);
     info!("Total Return: {:.2}%", metrics.total_return_pct);
+    if let Some(hold_return) = metrics.hold_to_expiry_return_pct {
+        info!("Hold-to-Expiry Return: {:.2}%", hold_return);
+    }
     info!("Annualized Return: {:.2}%", metrics.annualized_return_pct);
     info!("Max Drawdown: {:.2}%", metrics.max_drawdown_pct);
     info!("Sharpe Ratio: {:.2}", metrics.sharpe_ratio);
     info!("Sortino Ratio: {:.2}", metrics.sortino_ratio);
     info!("Total Trades: {}", metrics.total_trades);
+    if metrics.margin_calls > 0 {
+        info!("Margin Calls: {}", metrics.margin_calls);
+    }
     info!("Win Rate: {:.2}%", metrics.win_rate_pct);
+    if let Some(p_value) = metrics.win_rate_p_value()
+        && !metrics.is_statistically_significant(0.05)
+    {
+        warn!(
+            "Win rate over {} trades is indistinguishable from a coin flip (p = {:.3}); treat these metrics as noise",
+            metrics.total_trades, p_value
+        );
+    }
     info!("Profit Factor: {:.2}", metrics.profit_factor);
     info!("Avg Holding Period: {:.2} days", metrics.avg_holding_days);
+    info!("Theta Collected: ${:.2}", metrics.total_theta_collected);
+    if let Some(premium) = metrics.vol_risk_premium {
+        info!("Vol Risk Premium: {:.2} vol points", premium * 100.0);
+    }
     info!("=============================");
 
+    for line in backtest_runner.trade_blotter_string(10).lines() {
+        info!("{}", line);
+    }
+
     Ok(())
 }
 
@@ -79,39 +115,18 @@ async fn load_or_generate_options_data()
 -> Result<Vec<backtest::runner::EnhancedMarketEvent>, Box<dyn std::error::Error>> {
     info!("Attempting to fetch live options data from Alpha Vantage...");
 
-    if let Ok(api_key) = std::env::var("ALPHA_VANTAGE_API_KEY") {
-        let alpha_client = AlphaVantageClient::new(api_key);
-
-        let mut option_chains = Vec::new();
-        let tickers = [
-            "SPY", "QQQ", "XRT", "XBI", "EWZ", "XOP", "FXI", "XLP", "XLE",
-        ];
-
-        for ticker in tickers {
-            match alpha_client.fetch_options_chain(ticker).await {
-                Ok(live_event) => {
-                    info!("Successfully fetched live SPY options data from Alpha Vantage!");
-                    info!("Underlying price: ${:.2}", live_event.underlying_price);
-                    info!("Available expirations: {}", live_event.options_chains.len());
-                    info!(
-                        "Total options contracts: {}",
-                        live_event
-                            .options_chains
-                            .values()
-                            .map(|c| c.calls.len() + c.puts.len())
-                            .sum::<usize>()
-                    );
-
-                    option_chains.extend(vec![live_event]);
-                }
-                Err(e) => {
-                    warn!("Failed to fetch from Alpha Vantage: {}", e);
-                    continue;
-                }
-            }
-        }
-        Ok(option_chains)
-    } else {
-        Err("Alpha Vantage API key not found in environment variables".into())
-    }
+    let Some(api_key) = keys::resolve_api_key(
+        "ALPHA_VANTAGE_API_KEY",
+        "alpha_vantage",
+        keys::default_keyfile_path().as_deref(),
+    ) else {
+        return Err("Alpha Vantage API key not found in the environment or keyfile".into());
+    };
+
+    let alpha_client = AlphaVantageClient::new(api_key);
+    let tickers = [
+        "SPY", "QQQ", "XRT", "XBI", "EWZ", "XOP", "FXI", "XLP", "XLE",
+    ];
+
+    load_events(&alpha_client, &tickers).await
 }
@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+/// Standard normal cumulative distribution function.
+///
+/// Uses the Abramowitz-Stegun 26.2.17 rational approximation, accurate to 7.5e-8 and
+/// cheap enough to call in tight optimizer and Monte-Carlo loops.
+pub fn norm_cdf(x: f64) -> f64 {
+    const P: f64 = 0.231_641_9;
+    const B: [f64; 5] = [
+        0.319_381_530,
+        -0.356_563_782,
+        1.781_477_937,
+        -1.821_255_978,
+        1.330_274_429,
+    ];
+
+    let t = 1.0 / (1.0 + P * x.abs());
+    let poly = t * (B[0] + t * (B[1] + t * (B[2] + t * (B[3] + t * B[4]))));
+    let tail = norm_pdf(x) * poly;
+    if x >= 0.0 { 1.0 - tail } else { tail }
+}
+
+/// Standard normal probability density function
+pub fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * PI).sqrt()
+}
+
+/// Annualized volatility of the log returns between consecutive `prices`, with
+/// `periods_per_year` price observations per year. None with fewer than three prices
+/// (two returns) or a non-positive price.
+pub fn realized_volatility(prices: &[f64], periods_per_year: f64) -> Option<f64> {
+    if prices.len() < 3 || prices.iter().any(|price| *price <= 0.0) {
+        return None;
+    }
+
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .map(|pair| (pair[1] / pair[0]).ln())
+        .collect();
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    Some((variance * periods_per_year).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_norm_cdf_known_values() {
+        let cases = [
+            (0.0, 0.5),
+            (1.0, 0.841_344_746_068_542_9),
+            (1.96, 0.975_002_104_851_779_5),
+            (-1.96, 0.024_997_895_148_220_4),
+            (3.0, 0.998_650_101_968_369_9),
+        ];
+        for (x, expected) in cases {
+            assert!(
+                (norm_cdf(x) - expected).abs() < 1e-7,
+                "N({}) = {}, expected {}",
+                x,
+                norm_cdf(x),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_norm_cdf_is_symmetric() {
+        for x in [0.1, 0.5, 1.5, 2.5, 4.0] {
+            assert!((norm_cdf(x) + norm_cdf(-x) - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_realized_volatility_of_alternating_returns() {
+        // Returns alternate between +r and -r, so the sample std is r * sqrt(n / (n - 1))
+        let r: f64 = 0.01;
+        let prices: Vec<f64> = (0..5).map(|i| 100.0 * (r * (i % 2) as f64).exp()).collect();
+        let expected = r * (4.0_f64 / 3.0).sqrt() * 252.0_f64.sqrt();
+
+        let vol = realized_volatility(&prices, 252.0).unwrap();
+        assert!((vol - expected).abs() < 1e-12);
+        assert_eq!(realized_volatility(&prices[..2], 252.0), None);
+    }
+}
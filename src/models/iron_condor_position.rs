@@ -1,8 +1,137 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use uuid::Uuid;
 
-use crate::models::options_data::OptionsContract;
+use crate::models::options_data::{Greeks, OptionType, OptionsChain, OptionsContract};
+use crate::models::pricing::MarkSource;
+
+/// Direction of a leg
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    /// Exposure sign: +1 for a long leg, -1 for a short one
+    pub fn direction(self) -> f64 {
+        match self {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        }
+    }
+
+    /// The side that closes a leg opened on this side
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+
+    /// Price an order on this side fills at, `mid_offset` of the half-spread from the mid
+    /// towards the far side: at 1 buys pay the ask and sells get the bid
+    pub fn fill_price(self, contract: &OptionsContract, mid_offset: f64) -> f64 {
+        let mid = contract.mid();
+        match self {
+            Side::Buy => contract.ask - (1.0 - mid_offset) * (contract.ask - mid),
+            Side::Sell => contract.bid + (1.0 - mid_offset) * (mid - contract.bid),
+        }
+    }
+}
+
+/// One leg of a multi-leg position
+#[derive(Debug, Clone, Copy)]
+pub struct Leg<'a> {
+    pub contract: &'a OptionsContract,
+    pub side: Side,
+    pub quantity: u32,
+    /// Fraction of the half-spread from the mid that the fill lands at
+    pub mid_offset: f64,
+}
+
+impl Leg<'_> {
+    /// Fill for trading this leg at the current quote
+    pub fn fill(&self) -> LegFill {
+        LegFill::from_contract(
+            self.contract,
+            self.quantity,
+            self.side == Side::Sell,
+            self.side.fill_price(self.contract, self.mid_offset),
+        )
+    }
+
+    /// The same leg traded the other way, to close it
+    pub fn closing(&self) -> Self {
+        Self {
+            side: self.side.opposite(),
+            ..*self
+        }
+    }
+}
+
+/// Net cash flow of trading every leg at its quote (positive for a credit)
+pub fn net_premium(legs: &[Leg]) -> f64 {
+    legs.iter().map(|leg| leg.fill().cash_flow()).sum()
+}
+
+/// Fill record for a single leg of a multi-leg order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegFill {
+    /// Option type of the leg
+    pub option_type: OptionType,
+    /// Strike price of the leg
+    pub strike: f64,
+    /// True if this fill sold the contract, false if it bought it
+    pub is_sell: bool,
+    /// Number of contracts filled
+    pub quantity: u32,
+    /// Bid at the time of the fill
+    pub bid: f64,
+    /// Ask at the time of the fill
+    pub ask: f64,
+    /// Price the leg was filled at
+    pub price: f64,
+}
+
+impl LegFill {
+    fn from_contract(contract: &OptionsContract, quantity: u32, is_sell: bool, price: f64) -> Self {
+        Self {
+            option_type: contract.option_type,
+            strike: contract.strike,
+            is_sell,
+            quantity,
+            bid: contract.bid,
+            ask: contract.ask,
+            price,
+        }
+    }
+
+    /// Cash flow of this fill (positive when selling, negative when buying)
+    pub fn cash_flow(&self) -> f64 {
+        let signed_price = if self.is_sell {
+            self.price
+        } else {
+            -self.price
+        };
+        signed_price * self.quantity as f64
+    }
+
+    /// Cash flow the fill would have had at the quote midpoint
+    pub fn mid_cash_flow(&self) -> f64 {
+        LegFill {
+            price: (self.bid + self.ask) / 2.0,
+            ..self.clone()
+        }
+        .cash_flow()
+    }
+
+    /// Cost of filling away from the mid (positive when the fill was worse than mid)
+    pub fn slippage(&self) -> f64 {
+        self.mid_cash_flow() - self.cash_flow()
+    }
+}
 
 /// A complete iron condor position with real options contracts
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +142,9 @@ pub struct IronCondorPosition {
     pub underlying: String,
     /// Entry timestamp
     pub entry_time: DateTime<Utc>,
+    /// Underlying price at entry
+    #[serde(default)]
+    pub entry_underlying_price: f64,
     /// Exit timestamp (None if still open)
     pub exit_time: Option<DateTime<Utc>>,
 
@@ -35,9 +167,81 @@ pub struct IronCondorPosition {
 
     /// Reason for exit (if closed)
     pub exit_reason: Option<String>,
+
+    /// IV rank (0-1) of the underlying when the position was opened, if known
+    #[serde(default)]
+    pub entry_iv_rank: Option<f64>,
+    /// Fraction of the half-spread from the mid that the legs fill at (None = the far side)
+    #[serde(default)]
+    pub fill_mid_offset: Option<f64>,
+}
+
+/// Reasons an iron condor cannot be constructed
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum PositionError {
+    /// The legs would be opened for a net debit (or nothing) instead of a credit
+    #[error("net premium ${0:.2} is not a credit")]
+    NotACredit(f64),
+    /// A credit of at least the narrower spread's width is free money, so the quotes are bad
+    #[error("credit ${premium_per_contract:.2} per contract is not below the ${width:.2} width")]
+    CreditExceedsWidth {
+        premium_per_contract: f64,
+        width: f64,
+    },
+    /// The wing landed on the short strike, e.g. after snapping on a sparse chain: the
+    /// spread has no width to cap its loss
+    #[error("long {option_type:?} shares the {strike:.1} short strike")]
+    ZeroWidthSpread {
+        option_type: OptionType,
+        strike: f64,
+    },
+}
+
+/// Upward price shock applied by portfolio margin (OCC TIMS range for broad-based indexes)
+const PORTFOLIO_MARGIN_UP_SHOCK: f64 = 0.06;
+/// Downward price shock applied by portfolio margin
+const PORTFOLIO_MARGIN_DOWN_SHOCK: f64 = 0.08;
+/// Evenly spaced price points evaluated on each side of the shock range
+const PORTFOLIO_MARGIN_STEPS: u32 = 10;
+
+/// Broker margin methodology
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub enum MarginModel {
+    /// Strategy-based: the wider spread's width minus the credit received
+    RegT,
+    /// Risk-based: the worst loss when the underlying is shocked up and down from entry
+    Portfolio,
+}
+
+/// Decomposition of a position's P&L change into Greek contributions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PnlAttribution {
+    /// P&L from the first-order underlying move
+    pub delta: f64,
+    /// P&L from the second-order underlying move
+    pub gamma: f64,
+    /// P&L from time decay
+    pub theta: f64,
+    /// P&L from the implied volatility change
+    pub vega: f64,
+    /// Sum of all contributions
+    pub total: f64,
+}
+
+/// The short strike the underlying is closest to, and how far spot still is from it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TestedSide {
+    /// Side of the tested short
+    pub side: OptionType,
+    /// Dollars left to the short strike: negative once spot is through it
+    pub dollars: f64,
+    /// Absolute delta of the tested short, its distance to the money in deltas
+    pub delta: f64,
 }
 
 impl IronCondorPosition {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         underlying: String,
         short_call: OptionsContract,
@@ -46,25 +250,88 @@ impl IronCondorPosition {
         long_put: OptionsContract,
         quantity: u32,
         entry_time: DateTime<Utc>,
-    ) -> Self {
-        // Calculate net premium received (credit spread)
-        let entry_premium =
-            (short_call.bid + short_put.bid - long_call.ask - long_put.ask) * quantity as f64;
-
-        Self {
+        entry_underlying_price: f64,
+    ) -> Result<Self, PositionError> {
+        let mut position = Self {
             id: Uuid::new_v4(),
             underlying,
             entry_time,
+            entry_underlying_price,
             exit_time: None,
             short_call,
             long_call,
             short_put,
             long_put,
             quantity,
-            entry_premium,
+            entry_premium: 0.0,
             exit_premium: None,
             exit_reason: None,
+            entry_iv_rank: None,
+            fill_mid_offset: None,
+        };
+
+        for (short, long) in [
+            (&position.short_call, &position.long_call),
+            (&position.short_put, &position.long_put),
+        ] {
+            if short.strike == long.strike {
+                return Err(PositionError::ZeroWidthSpread {
+                    option_type: short.option_type,
+                    strike: short.strike,
+                });
+            }
+        }
+
+        // Net premium received (credit spread)
+        position.entry_premium = net_premium(&position.legs());
+        if position.entry_premium <= 0.0 {
+            return Err(PositionError::NotACredit(position.entry_premium));
+        }
+        let premium_per_contract = position.entry_premium / quantity.max(1) as f64;
+        let width = position
+            .call_spread_width()
+            .min(position.put_spread_width());
+        if premium_per_contract >= width {
+            return Err(PositionError::CreditExceedsWidth {
+                premium_per_contract,
+                width,
+            });
         }
+
+        Ok(position)
+    }
+
+    /// The four legs as opened: short call, long call, short put, long put
+    pub fn legs(&self) -> [Leg<'_>; 4] {
+        let leg = |contract, side| Leg {
+            contract,
+            side,
+            quantity: self.quantity,
+            mid_offset: self.fill_mid_offset.unwrap_or(1.0),
+        };
+        [
+            leg(&self.short_call, Side::Sell),
+            leg(&self.long_call, Side::Buy),
+            leg(&self.short_put, Side::Sell),
+            leg(&self.long_put, Side::Buy),
+        ]
+    }
+
+    /// Top up the position with `added` contracts opened for a total `premium`. The entry
+    /// premium stays the total credit, so its per-contract average blends both fills.
+    pub fn add_contracts(&mut self, added: u32, premium: f64) {
+        self.quantity += added;
+        self.entry_premium += premium;
+    }
+
+    /// Leg fills for opening the position (sell the shorts at bid, buy the longs at ask)
+    pub fn entry_fills(&self) -> Vec<LegFill> {
+        self.legs().iter().map(Leg::fill).collect()
+    }
+
+    /// Leg fills for closing the position (buy the shorts at ask, sell the longs at bid)
+    pub fn exit_fills(&self) -> Vec<LegFill> {
+        self.legs().iter().map(|leg| leg.closing().fill()).collect()
     }
 
     /// Check if the position is still open
@@ -96,6 +363,108 @@ impl IronCondorPosition {
         max_call_loss.max(max_put_loss) - self.entry_premium
     }
 
+    /// Capital a broker holds against the position: only one side can finish in the
+    /// money, so the wider spread's width
+    pub fn margin_requirement(&self) -> f64 {
+        self.call_spread_width().max(self.put_spread_width()) * self.quantity as f64
+    }
+
+    /// Cash a cash-secured account sets aside: the full width of both spreads
+    pub fn cash_secured_requirement(&self) -> f64 {
+        (self.call_spread_width() + self.put_spread_width()) * self.quantity as f64
+    }
+
+    /// Margin required for the position under the given broker model
+    #[allow(dead_code)]
+    pub fn margin(&self, model: MarginModel) -> f64 {
+        match model {
+            MarginModel::RegT => self.max_loss().max(0.0),
+            MarginModel::Portfolio => {
+                let spot = self.entry_underlying_price;
+                let steps = PORTFOLIO_MARGIN_STEPS as f64;
+                (0..=PORTFOLIO_MARGIN_STEPS)
+                    .flat_map(|step| {
+                        let fraction = step as f64 / steps;
+                        [
+                            spot * (1.0 + PORTFOLIO_MARGIN_UP_SHOCK * fraction),
+                            spot * (1.0 - PORTFOLIO_MARGIN_DOWN_SHOCK * fraction),
+                        ]
+                    })
+                    .map(|price| self.settlement_cost(price) - self.entry_premium)
+                    .fold(0.0, f64::max)
+            }
+        }
+    }
+
+    /// Net debit to settle the position at expiration (intrinsic value of the spreads)
+    pub fn settlement_cost(&self, settlement_price: f64) -> f64 {
+        let intrinsic = |contract: &OptionsContract| contract.intrinsic_value(settlement_price);
+
+        let call_spread = intrinsic(&self.short_call) - intrinsic(&self.long_call);
+        let put_spread = intrinsic(&self.short_put) - intrinsic(&self.long_put);
+
+        (call_spread + put_spread) * self.quantity as f64
+    }
+
+    /// Expiration P&L at `steps + 1` evenly spaced prices from `price_min` to
+    /// `price_max`, for plotting the payoff diagram. Flat at max profit between the
+    /// shorts, sloping through each short-to-long spread, and flat at the spread's max
+    /// loss beyond the wings.
+    #[allow(dead_code)]
+    pub fn payoff_curve(&self, price_min: f64, price_max: f64, steps: usize) -> Vec<(f64, f64)> {
+        let step = if steps == 0 {
+            0.0
+        } else {
+            (price_max - price_min) / steps as f64
+        };
+        (0..=steps)
+            .map(|i| {
+                let price = price_min + step * i as f64;
+                (price, self.entry_premium - self.settlement_cost(price))
+            })
+            .collect()
+    }
+
+    /// The short strike the underlying is closest to, with the distance left to it in
+    /// dollars and in deltas. None when spot sits exactly midway between the shorts.
+    #[allow(dead_code)]
+    pub fn tested_side(&self, spot: f64) -> Option<TestedSide> {
+        let call_distance = self.short_call.strike - spot;
+        let put_distance = spot - self.short_put.strike;
+        let (short, dollars) = if call_distance < put_distance {
+            (&self.short_call, call_distance)
+        } else if put_distance < call_distance {
+            (&self.short_put, put_distance)
+        } else {
+            return None;
+        };
+        Some(TestedSide {
+            side: short.option_type,
+            dollars,
+            delta: short.greeks.delta.abs(),
+        })
+    }
+
+    /// Leg fills for closing the position at intrinsic value with the underlying at
+    /// `settlement_price`. Their cash flows add up to minus `settlement_cost`.
+    pub fn settlement_fills(&self, settlement_price: f64) -> Vec<LegFill> {
+        self.legs()
+            .iter()
+            .map(|leg| {
+                let intrinsic = leg.contract.intrinsic_value(settlement_price);
+                LegFill {
+                    option_type: leg.contract.option_type,
+                    strike: leg.contract.strike,
+                    is_sell: leg.closing().side == Side::Sell,
+                    quantity: leg.quantity,
+                    bid: intrinsic,
+                    ask: intrinsic,
+                    price: intrinsic,
+                }
+            })
+            .collect()
+    }
+
     /// Calculate current P&L based on current option prices
     pub fn calculate_pnl(&self, current_underlying_price: f64) -> f64 {
         if let Some(exit_premium) = self.exit_premium {
@@ -136,12 +505,102 @@ impl IronCondorPosition {
         self.entry_premium + call_spread_pnl + put_spread_pnl
     }
 
+    /// Mark-to-market P&L valuing each leg from the current chain with `source`, with
+    /// the underlying at `spot`. None if the chain is for a different expiration or is
+    /// missing a leg.
+    pub fn mark_pnl(
+        &self,
+        chain: &OptionsChain,
+        source: MarkSource,
+        spot: f64,
+        now: DateTime<Utc>,
+    ) -> Option<f64> {
+        if chain.expiration != self.short_call.expiration {
+            return None;
+        }
+
+        let mark = |contract: Option<&OptionsContract>| contract.map(|c| source.mark(c, spot, now));
+
+        let close_cost = mark(chain.get_call(self.short_call.strike))?
+            - mark(chain.get_call(self.long_call.strike))?
+            + mark(chain.get_put(self.short_put.strike))?
+            - mark(chain.get_put(self.long_put.strike))?;
+
+        Some(self.entry_premium - close_cost * self.quantity as f64)
+    }
+
+
+    /// Attribute the P&L change since entry to delta, gamma, theta and vega using each
+    /// leg's entry Greeks. Theta is per day and vega per 1 point (1%) of IV.
+    #[allow(dead_code)]
+    pub fn pnl_attribution(
+        &self,
+        new_spot: f64,
+        new_iv: f64,
+        now: DateTime<Utc>,
+    ) -> PnlAttribution {
+        let spot_change = new_spot - self.entry_underlying_price;
+        let days_elapsed = (now - self.entry_time).num_seconds() as f64 / 86_400.0;
+
+        let mut attribution = PnlAttribution {
+            delta: 0.0,
+            gamma: 0.0,
+            theta: 0.0,
+            vega: 0.0,
+            total: 0.0,
+        };
+
+        for Leg {
+            contract,
+            side,
+            quantity,
+            ..
+        } in self.legs()
+        {
+            let size = side.direction() * quantity as f64;
+            let iv_points = (new_iv - contract.implied_volatility) * 100.0;
+
+            attribution.delta += size * contract.greeks.delta * spot_change;
+            attribution.gamma += size * 0.5 * contract.greeks.gamma * spot_change.powi(2);
+            attribution.theta += size * contract.greeks.theta * days_elapsed;
+            attribution.vega += size * contract.greeks.vega * iv_points;
+        }
+
+        attribution.total =
+            attribution.delta + attribution.gamma + attribution.theta + attribution.vega;
+        attribution
+    }
+
+    /// Greeks of the whole position (short legs count negative) from each leg's entry Greeks
+    pub fn net_greeks(&self) -> Greeks {
+        let mut net = Greeks::default();
+        for Leg {
+            contract,
+            side,
+            quantity,
+            ..
+        } in self.legs()
+        {
+            let size = side.direction() * quantity as f64;
+            net.delta += size * contract.greeks.delta;
+            net.gamma += size * contract.greeks.gamma;
+            net.theta += size * contract.greeks.theta;
+            net.vega += size * contract.greeks.vega;
+            net.rho += size * contract.greeks.rho;
+        }
+        net
+    }
 
     /// Get the profit percentage based on max profit
+    #[allow(dead_code)]
     pub fn profit_percentage(&self, current_underlying_price: f64) -> f64 {
-        let current_pnl = self.calculate_pnl(current_underlying_price);
+        self.pnl_percentage(self.calculate_pnl(current_underlying_price))
+    }
+
+    /// Express a P&L as a percentage of max profit
+    pub fn pnl_percentage(&self, pnl: f64) -> f64 {
         if self.max_profit() > 0.0 {
-            (current_pnl / self.max_profit()) * 100.0
+            (pnl / self.max_profit()) * 100.0
         } else {
             0.0
         }
@@ -172,7 +631,47 @@ impl IronCondorPosition {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::options_data::{OptionType, OptionsContract, OptionsContractParams};
+    use crate::models::options_data::{Greeks, OptionType, OptionsContract, OptionsContractParams};
+
+    /// SPY contract with the underlying at 400, priced at `now` and expiring `days` later
+    fn test_contract(
+        option_type: OptionType,
+        strike: f64,
+        now: DateTime<Utc>,
+        days: i64,
+    ) -> OptionsContract {
+        OptionsContract::new(OptionsContractParams {
+            underlying: "SPY".to_string(),
+            option_type,
+            strike,
+            expiration: now + chrono::Duration::days(days),
+            underlying_price: 400.0,
+            implied_vol: 0.20,
+            risk_free_rate: 0.05,
+            current_time: now,
+        })
+    }
+
+    /// Iron condor on `test_contract`s with its short call, long call, short put and
+    /// long put at `strikes`
+    fn test_position(
+        strikes: [f64; 4],
+        quantity: u32,
+        now: DateTime<Utc>,
+        days: i64,
+    ) -> Result<IronCondorPosition, PositionError> {
+        let [short_call, long_call, short_put, long_put] = strikes;
+        IronCondorPosition::new(
+            "SPY".to_string(),
+            test_contract(OptionType::Call, short_call, now, days),
+            test_contract(OptionType::Call, long_call, now, days),
+            test_contract(OptionType::Put, short_put, now, days),
+            test_contract(OptionType::Put, long_put, now, days),
+            quantity,
+            now,
+            400.0,
+        )
+    }
 
     #[test]
     fn test_iron_condor_creation() {
@@ -239,7 +738,9 @@ mod tests {
             long_put,
             1,
             now,
-        );
+            400.0,
+        )
+        .unwrap();
 
         
         assert!(position.is_open());
@@ -248,4 +749,152 @@ mod tests {
         assert!(position.max_profit() > 0.0);
         assert!(position.max_loss() > 0.0);
     }
+
+    #[test]
+    fn test_margin_reg_t_and_portfolio() {
+        // $20 wide call spread 6% away is reached by the shocks, the put side 8% away only partly
+        let position = test_position([410.0, 430.0, 380.0, 360.0], 1, Utc::now(), 7).unwrap();
+        let credit = position.entry_premium;
+
+        assert!((position.margin(MarginModel::RegT) - (20.0 - credit)).abs() < 1e-9);
+        // Worst shock is +6% (424): 14 points through the short call
+        assert!((position.margin(MarginModel::Portfolio) - (14.0 - credit)).abs() < 1e-9);
+        assert!(position.margin(MarginModel::Portfolio) < position.margin(MarginModel::RegT));
+    }
+
+    #[test]
+    fn test_pnl_attribution_pure_time_decay_is_theta() {
+        let now = Utc::now();
+        let greeks = |delta, gamma, theta, vega| Greeks {
+            delta,
+            gamma,
+            theta,
+            vega,
+            rho: 0.0,
+        };
+
+        let mut position = test_position([410.0, 415.0, 390.0, 385.0], 1, now, 7).unwrap();
+        position.short_call.greeks = greeks(0.20, 0.03, -0.12, 0.10);
+        position.long_call.greeks = greeks(0.10, 0.02, -0.07, 0.06);
+        position.short_put.greeks = greeks(-0.20, 0.03, -0.11, 0.10);
+        position.long_put.greeks = greeks(-0.10, 0.02, -0.06, 0.06);
+
+        let attribution = position.pnl_attribution(400.0, 0.20, now + chrono::Duration::days(1));
+
+        // Short premium collects the net theta of the short legs over the long legs
+        assert!((attribution.theta - 0.10).abs() < 1e-9);
+        assert_eq!(attribution.delta, 0.0);
+        assert_eq!(attribution.gamma, 0.0);
+        assert_eq!(attribution.vega, 0.0);
+        assert!((attribution.total - attribution.theta).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_settlement_cost() {
+        let position = test_position([410.0, 415.0, 390.0, 385.0], 2, Utc::now(), 1).unwrap();
+
+        assert_eq!(position.settlement_cost(400.0), 0.0);
+        assert_eq!(position.settlement_cost(412.0), 4.0);
+        assert_eq!(position.settlement_cost(430.0), 10.0);
+        assert_eq!(position.settlement_cost(387.0), 6.0);
+        assert_eq!(position.settlement_cost(300.0), 10.0);
+    }
+
+    #[test]
+    fn test_debit_construction_is_rejected() {
+        // Longs closer to the money than the shorts makes the trade a debit
+        let result = test_position([415.0, 410.0, 385.0, 390.0], 1, Utc::now(), 1);
+
+        assert!(matches!(
+            result,
+            Err(PositionError::NotACredit(premium)) if premium < 0.0
+        ));
+    }
+
+    #[test]
+    fn test_credit_wider_than_spread_is_rejected() {
+        let now = Utc::now();
+
+        // Crossed quote on the short call: a $6 bid over a $1 ask on a $5-wide spread
+        let mut short_call = test_contract(OptionType::Call, 410.0, now, 1);
+        short_call.bid = 6.0;
+        short_call.ask = 1.0;
+        let result = IronCondorPosition::new(
+            "SPY".to_string(),
+            short_call,
+            test_contract(OptionType::Call, 415.0, now, 1),
+            test_contract(OptionType::Put, 390.0, now, 1),
+            test_contract(OptionType::Put, 385.0, now, 1),
+            2,
+            now,
+            400.0,
+        );
+
+        assert!(matches!(
+            result,
+            Err(PositionError::CreditExceedsWidth { premium_per_contract, width })
+                if premium_per_contract > 5.0 && width == 5.0
+        ));
+    }
+
+    #[test]
+    fn test_leg_based_premium_matches_credit_formula() {
+        let position = test_position([410.0, 415.0, 390.0, 385.0], 3, Utc::now(), 7).unwrap();
+
+        let formula = (position.short_call.bid + position.short_put.bid
+            - position.long_call.ask
+            - position.long_put.ask)
+            * 3.0;
+        assert!((position.entry_premium - formula).abs() < 1e-12);
+        assert!((net_premium(&position.legs()) - formula).abs() < 1e-12);
+
+        // Closing trades every leg the other way across the spread
+        let closing: Vec<Leg> = position.legs().iter().map(Leg::closing).collect();
+        let close_formula = (position.long_call.bid + position.long_put.bid
+            - position.short_call.ask
+            - position.short_put.ask)
+            * 3.0;
+        assert!((net_premium(&closing) - close_formula).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_tested_side_near_short_call() {
+        let mut position = test_position([410.0, 415.0, 390.0, 385.0], 1, Utc::now(), 7).unwrap();
+        position.short_call.greeks.delta = 0.30;
+        position.short_put.greeks.delta = -0.25;
+
+        let tested = position.tested_side(407.5).unwrap();
+        assert_eq!((tested.side, tested.dollars), (OptionType::Call, 2.5));
+        assert_eq!(tested.delta, 0.30);
+        let tested = position.tested_side(412.0).unwrap();
+        assert_eq!((tested.side, tested.dollars), (OptionType::Call, -2.0));
+        let tested = position.tested_side(393.0).unwrap();
+        assert_eq!((tested.side, tested.dollars), (OptionType::Put, 3.0));
+        assert_eq!(tested.delta, 0.25);
+        assert_eq!(position.tested_side(400.0), None);
+    }
+
+    #[test]
+    fn test_payoff_curve_shape() {
+        let position = test_position([410.0, 415.0, 390.0, 385.0], 1, Utc::now(), 7).unwrap();
+
+        let curve = position.payoff_curve(370.0, 430.0, 60);
+        assert_eq!(curve.len(), 61);
+        assert_eq!(curve.first().unwrap().0, 370.0);
+        assert_eq!(curve.last().unwrap().0, 430.0);
+
+        let max_loss = position.entry_premium - 5.0;
+        for &(price, pnl) in &curve {
+            if (390.0..=410.0).contains(&price) {
+                assert!((pnl - position.max_profit()).abs() < 1e-9, "{price}: {pnl}");
+            } else if price <= 385.0 || price >= 415.0 {
+                assert!((pnl - max_loss).abs() < 1e-9, "{price}: {pnl}");
+            } else {
+                assert!(
+                    pnl < position.max_profit() && pnl > max_loss,
+                    "{price}: {pnl}"
+                );
+            }
+        }
+    }
 }
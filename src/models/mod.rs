@@ -1,2 +1,4 @@
 pub mod iron_condor_position;
 pub mod options_data;
+pub mod pricing;
+pub mod product;
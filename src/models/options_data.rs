@@ -1,6 +1,16 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use thiserror::Error;
+
+/// Spread multiple added at the moment of expiration (a 1x spread becomes 4x)
+const EXPIRY_SPREAD_WIDENING: f64 = 3.0;
+/// Spreads start widening this many hours before expiration
+const EXPIRY_WIDENING_HOURS: f64 = 24.0;
+/// Extra widening per unit of relative distance from spot (|strike / spot - 1|)
+const MONEYNESS_SPREAD_WIDENING: f64 = 10.0;
+/// DTE the synthetic time values are calibrated at; other DTEs scale by sqrt(DTE / this)
+const SYNTHETIC_REFERENCE_DTE: f64 = 30.0;
 
 /// Option type (Call or Put)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -9,6 +19,17 @@ pub enum OptionType {
     Put,
 }
 
+impl OptionType {
+    /// Value of exercising an option of this type struck at `strike` with the
+    /// underlying at `spot`
+    pub fn intrinsic_value(self, strike: f64, spot: f64) -> f64 {
+        match self {
+            OptionType::Call => (spot - strike).max(0.0),
+            OptionType::Put => (strike - spot).max(0.0),
+        }
+    }
+}
+
 /// The Greeks for options pricing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Greeks {
@@ -86,22 +107,31 @@ impl OptionsContract {
         let dte = (params.expiration - params.current_time).num_days().max(0) as u32;
 
         // This is not robust at all, but for backtesting this will work
-        let intrinsic = match params.option_type {
-            OptionType::Call => (params.underlying_price - params.strike).max(0.0),
-            OptionType::Put => (params.strike - params.underlying_price).max(0.0),
-        };
+        let intrinsic = params
+            .option_type
+            .intrinsic_value(params.strike, params.underlying_price);
 
         let distance_from_money = (params.strike - params.underlying_price).abs();
         let time_value = if params.expiration > params.current_time {
             // Closer to money = higher time value, farther = lower time value
             // Ensure short options are worth more than long options
-            f64::max(3.0 - (distance_from_money * 0.1), 0.5)
+            let moneyness_value = f64::max(3.0 - (distance_from_money * 0.1), 0.5);
+            // Time value decays with the square root of the (fractional) time left
+            let days_left =
+                (params.expiration - params.current_time).num_seconds() as f64 / 86_400.0;
+            moneyness_value * (days_left / SYNTHETIC_REFERENCE_DTE).sqrt()
         } else {
             // Even expired options get minimum value for testing
             0.5
         };
 
-        let price = (intrinsic + time_value).max(0.01);
+        // A call can't be worth more than the stock, nor a put more than its strike.
+        // Both caps keep prices monotonic in strike.
+        let upper_bound = match params.option_type {
+            OptionType::Call => params.underlying_price,
+            OptionType::Put => params.strike,
+        };
+        let price = (intrinsic + time_value).min(upper_bound).max(0.01);
 
         // Simple bid-ask spread
         let spread = (price * 0.03).max(0.01);
@@ -124,6 +154,92 @@ impl OptionsContract {
             timestamp: params.current_time,
         }
     }
+
+    /// Value of exercising now with the underlying at `spot`
+    pub fn intrinsic_value(&self, spot: f64) -> f64 {
+        self.option_type.intrinsic_value(self.strike, spot)
+    }
+
+    /// True if the quote is usable: finite, not crossed, and with a positive ask
+    pub fn is_valid_market(&self) -> bool {
+        self.bid.is_finite() && self.ask.is_finite() && self.ask >= self.bid && self.ask > 0.0
+    }
+
+    /// Midpoint of the bid and ask
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+
+    /// Bid-ask spread, floored at zero for crossed quotes
+    pub fn spread(&self) -> f64 {
+        (self.ask - self.bid).max(0.0)
+    }
+
+    /// Scale the bid-ask spread around the mid by `multiplier`, keeping the bid non-negative
+    pub fn widen_spread(&mut self, multiplier: f64) {
+        let mid = self.mid();
+        let half_spread = self.spread() * multiplier / 2.0;
+        self.bid = (mid - half_spread).max(0.0);
+        self.ask = mid + half_spread;
+    }
+
+    /// Spread as a fraction of the mid (None when the mid is not positive)
+    #[allow(dead_code)]
+    pub fn spread_pct(&self) -> Option<f64> {
+        let mid = self.mid();
+        (mid > 0.0).then(|| self.spread() / mid)
+    }
+}
+
+/// Static no-arbitrage condition broken by a chain's quotes (compared at the mids)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ArbitrageViolation {
+    /// A call is worth more than the call at the next lower strike
+    CallNotMonotonic {
+        lower_strike: f64,
+        higher_strike: f64,
+    },
+    /// A put is worth less than the put at the next lower strike
+    PutNotMonotonic {
+        lower_strike: f64,
+        higher_strike: f64,
+    },
+    /// Adjacent strikes are priced further apart than the strikes themselves
+    SpreadWiderThanStrikes {
+        option_type: OptionType,
+        lower_strike: f64,
+        higher_strike: f64,
+    },
+    /// A contract is worth less than its intrinsic value
+    BelowIntrinsic {
+        option_type: OptionType,
+        strike: f64,
+    },
+    /// A call is worth more than the underlying, or a put more than its strike
+    AboveUpperBound {
+        option_type: OptionType,
+        strike: f64,
+    },
+}
+
+/// Which source wins when both chains in a merge carry a field. A field missing from
+/// one side (zero Greeks, no quote) is always taken from the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub enum MergeStrategy {
+    /// Greeks and IV from `other` wherever its delta is nonzero, quotes from `self`
+    PreferNonZeroGreeks,
+    /// Quotes from whichever contract has the tighter spread, Greeks and IV from `self`
+    PreferTighterSpread,
+}
+
+/// Reasons two chains cannot be merged
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MergeError {
+    #[error("chains are for different underlyings ({0} vs {1})")]
+    UnderlyingMismatch(String, String),
+    #[error("chains are for different expirations ({0} vs {1})")]
+    ExpirationMismatch(DateTime<Utc>, DateTime<Utc>),
 }
 
 /// Options chain for a specific expiration
@@ -135,7 +251,10 @@ pub struct OptionsChain {
     pub expiration: DateTime<Utc>,
     /// Current underlying price
     pub underlying_price: f64,
-    /// All options contracts for this expiration (strike -> contract)
+    /// True if `underlying_price` was estimated rather than quoted
+    #[serde(default)]
+    pub underlying_price_estimated: bool,
+    /// All options contracts for this expiration, keyed by `OptionsChain::strike_key`
     pub calls: HashMap<u32, OptionsContract>,
     pub puts: HashMap<u32, OptionsContract>,
     /// Timestamp of this chain
@@ -143,6 +262,11 @@ pub struct OptionsChain {
 }
 
 impl OptionsChain {
+    /// Map key of a strike: the strike in cents, so $0.50 strikes stay distinct
+    pub fn strike_key(strike: f64) -> u32 {
+        (strike * 100.0).round() as u32
+    }
+
     /// Update all options in the chain with new underlying price
     #[allow(dead_code)]
     pub fn update(&mut self, new_underlying_price: f64, current_time: DateTime<Utc>) {
@@ -160,13 +284,565 @@ impl OptionsChain {
         }
     }
 
+    /// Widen every quote's spread as expiration approaches, more so away from the money,
+    /// mimicking how market makers pull liquidity in the final hours of 0DTE trading.
+    /// Chains a day or more from expiration are left untouched.
+    pub fn widen_spreads_for_expiry(&mut self) {
+        let hours_left = (self.expiration - self.timestamp).num_seconds() as f64 / 3_600.0;
+        let time_weight = (1.0 - hours_left.max(0.0) / EXPIRY_WIDENING_HOURS).max(0.0);
+        if time_weight == 0.0 {
+            return;
+        }
+
+        let spot = self.underlying_price;
+        for contract in self.calls.values_mut().chain(self.puts.values_mut()) {
+            let moneyness = (contract.strike / spot - 1.0).abs();
+            let multiplier = 1.0
+                + EXPIRY_SPREAD_WIDENING
+                    * time_weight.powi(2)
+                    * (1.0 + MONEYNESS_SPREAD_WIDENING * moneyness);
+            contract.widen_spread(multiplier);
+        }
+    }
+
+    /// Combine `other`'s contracts into this chain, e.g. Greeks from one provider with
+    /// quotes from another. Strikes only `other` lists are added as they are.
+    #[allow(dead_code)]
+    pub fn merge(
+        &mut self,
+        other: &OptionsChain,
+        strategy: MergeStrategy,
+    ) -> Result<(), MergeError> {
+        if !self.underlying.eq_ignore_ascii_case(&other.underlying) {
+            return Err(MergeError::UnderlyingMismatch(
+                self.underlying.clone(),
+                other.underlying.clone(),
+            ));
+        }
+        if self.expiration != other.expiration {
+            return Err(MergeError::ExpirationMismatch(
+                self.expiration,
+                other.expiration,
+            ));
+        }
+
+        for (contracts, other_contracts) in [
+            (&mut self.calls, &other.calls),
+            (&mut self.puts, &other.puts),
+        ] {
+            for (strike, theirs) in other_contracts {
+                match contracts.get_mut(strike) {
+                    Some(ours) => merge_contract(ours, theirs, strategy),
+                    None => {
+                        contracts.insert(*strike, theirs.clone());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Get call option by strike
     pub fn get_call(&self, strike: f64) -> Option<&OptionsContract> {
-        self.calls.get(&(strike as u32))
+        self.calls.get(&Self::strike_key(strike))
     }
 
     /// Get put option by strike
     pub fn get_put(&self, strike: f64) -> Option<&OptionsContract> {
-        self.puts.get(&(strike as u32))
+        self.puts.get(&Self::strike_key(strike))
+    }
+
+    /// Get call option by strike for in-place edits
+    #[allow(dead_code)]
+    pub fn get_call_mut(&mut self, strike: f64) -> Option<&mut OptionsContract> {
+        self.calls.get_mut(&Self::strike_key(strike))
+    }
+
+    /// Get put option by strike for in-place edits
+    #[allow(dead_code)]
+    pub fn get_put_mut(&mut self, strike: f64) -> Option<&mut OptionsContract> {
+        self.puts.get_mut(&Self::strike_key(strike))
+    }
+
+    /// Calls in ascending strike order
+    #[allow(dead_code)]
+    pub fn calls_sorted(&self) -> Vec<&OptionsContract> {
+        Self::sorted_by_strike(&self.calls)
+    }
+
+    /// Puts in ascending strike order
+    #[allow(dead_code)]
+    pub fn puts_sorted(&self) -> Vec<&OptionsContract> {
+        Self::sorted_by_strike(&self.puts)
+    }
+
+    fn sorted_by_strike(contracts: &HashMap<u32, OptionsContract>) -> Vec<&OptionsContract> {
+        let mut sorted: Vec<&OptionsContract> = contracts.values().collect();
+        sorted.sort_by(|a, b| a.strike.total_cmp(&b.strike));
+        sorted
+    }
+
+    /// True if any contract carries a nonzero delta. Sources without Greeks leave them
+    /// all at zero.
+    pub fn has_greeks(&self) -> bool {
+        self.calls
+            .values()
+            .chain(self.puts.values())
+            .any(|contract| contract.greeks.delta != 0.0)
+    }
+
+    /// Average implied volatility of the call and put nearest the underlying price
+    #[allow(dead_code)]
+    pub fn atm_iv(&self) -> Option<f64> {
+        let price = self.underlying_price;
+        let ivs: Vec<f64> = [
+            Self::nearest_contract(&self.calls, price),
+            Self::nearest_contract(&self.puts, price),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|contract| contract.implied_volatility)
+        .collect();
+
+        if ivs.is_empty() {
+            None
+        } else {
+            Some(ivs.iter().sum::<f64>() / ivs.len() as f64)
+        }
+    }
+
+    /// Every static arbitrage in the chain: call mids must not rise with strike, put mids
+    /// must not fall, adjacent strikes can't differ by more than their distance, and every
+    /// contract must trade between intrinsic value and the underlying (calls) or strike (puts)
+    #[allow(dead_code)]
+    pub fn check_arbitrage(&self) -> Vec<ArbitrageViolation> {
+        const TOLERANCE: f64 = 1e-9;
+        let spot = self.underlying_price;
+        let mut violations = Vec::new();
+
+        for (option_type, sorted) in [
+            (OptionType::Call, self.calls_sorted()),
+            (OptionType::Put, self.puts_sorted()),
+        ] {
+            for contract in &sorted {
+                let intrinsic = contract.intrinsic_value(spot);
+                let upper_bound = match option_type {
+                    OptionType::Call => spot,
+                    OptionType::Put => contract.strike,
+                };
+                if contract.mid() < intrinsic - TOLERANCE {
+                    violations.push(ArbitrageViolation::BelowIntrinsic {
+                        option_type,
+                        strike: contract.strike,
+                    });
+                }
+                if contract.mid() > upper_bound + TOLERANCE {
+                    violations.push(ArbitrageViolation::AboveUpperBound {
+                        option_type,
+                        strike: contract.strike,
+                    });
+                }
+            }
+
+            for pair in sorted.windows(2) {
+                let (lower, higher) = (pair[0], pair[1]);
+                let (lower_strike, higher_strike) = (lower.strike, higher.strike);
+                // Positive when the contract closer to the money is worth more, as it should be
+                let price_step = match option_type {
+                    OptionType::Call => lower.mid() - higher.mid(),
+                    OptionType::Put => higher.mid() - lower.mid(),
+                };
+
+                if price_step < -TOLERANCE {
+                    violations.push(match option_type {
+                        OptionType::Call => ArbitrageViolation::CallNotMonotonic {
+                            lower_strike,
+                            higher_strike,
+                        },
+                        OptionType::Put => ArbitrageViolation::PutNotMonotonic {
+                            lower_strike,
+                            higher_strike,
+                        },
+                    });
+                }
+                if price_step > higher_strike - lower_strike + TOLERANCE {
+                    violations.push(ArbitrageViolation::SpreadWiderThanStrikes {
+                        option_type,
+                        lower_strike,
+                        higher_strike,
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Total open interest plus volume across all contracts, as a liquidity proxy
+    pub fn total_liquidity(&self) -> u64 {
+        self.calls
+            .values()
+            .chain(self.puts.values())
+            .map(|contract| contract.open_interest as u64 + contract.volume as u64)
+            .sum()
+    }
+
+    /// Smallest gap between adjacent listed strikes (e.g. 5.0 for a $5-increment index chain)
+    pub fn strike_increment(&self) -> Option<f64> {
+        let mut strikes: Vec<f64> = self
+            .calls
+            .values()
+            .chain(self.puts.values())
+            .map(|contract| contract.strike)
+            .collect();
+        strikes.sort_by(|a, b| a.total_cmp(b));
+        strikes.dedup();
+
+        strikes
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .filter(|gap| *gap > f64::EPSILON)
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// The `n` call strikes and `n` put strikes closest to `spot`, nearest first
+    /// (fewer when the chain lists fewer). Equidistant strikes keep ascending order.
+    #[allow(dead_code)]
+    pub fn nearest_strikes(&self, spot: f64, n: usize) -> (Vec<f64>, Vec<f64>) {
+        let nearest = |contracts| {
+            Self::by_distance(contracts, spot)
+                .into_iter()
+                .take(n)
+                .map(|contract| contract.strike)
+                .collect()
+        };
+        (nearest(&self.calls), nearest(&self.puts))
+    }
+
+    /// Contract whose strike is closest to the given price
+    fn nearest_contract(
+        contracts: &HashMap<u32, OptionsContract>,
+        price: f64,
+    ) -> Option<&OptionsContract> {
+        Self::by_distance(contracts, price).into_iter().next()
+    }
+
+    /// Contracts ordered by strike distance from `price`, ties in ascending strike order
+    fn by_distance(contracts: &HashMap<u32, OptionsContract>, price: f64) -> Vec<&OptionsContract> {
+        let mut sorted = Self::sorted_by_strike(contracts);
+        sorted.sort_by(|a, b| {
+            (a.strike - price)
+                .abs()
+                .total_cmp(&(b.strike - price).abs())
+        });
+        sorted
+    }
+}
+
+/// Merge one strike's fields from `theirs` into `ours` following `strategy`
+fn merge_contract(ours: &mut OptionsContract, theirs: &OptionsContract, strategy: MergeStrategy) {
+    let has_quote = |contract: &OptionsContract| contract.ask > 0.0;
+    let has_greeks = |contract: &OptionsContract| contract.greeks.delta != 0.0;
+
+    let take_quotes = has_quote(theirs)
+        && (!has_quote(ours)
+            || (strategy == MergeStrategy::PreferTighterSpread && theirs.spread() < ours.spread()));
+    if take_quotes {
+        ours.bid = theirs.bid;
+        ours.ask = theirs.ask;
+        ours.last_price = theirs.last_price;
+        ours.volume = theirs.volume;
+        ours.open_interest = theirs.open_interest;
+    }
+
+    let take_greeks =
+        has_greeks(theirs) && (!has_greeks(ours) || strategy == MergeStrategy::PreferNonZeroGreeks);
+    if take_greeks {
+        ours.greeks = theirs.greeks.clone();
+        ours.implied_volatility = theirs.implied_volatility;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::strike_chain;
+
+    /// One-day chain with $5 strikes from 390 to 410, each with a distinct IV so the
+    /// ATM pick is observable
+    fn iv_skewed_chain(underlying_price: f64) -> OptionsChain {
+        let strikes = (390..=410).step_by(5).map(f64::from);
+        let mut chain = strike_chain(underlying_price, Utc::now(), 1, strikes);
+        for contract in chain.calls.values_mut().chain(chain.puts.values_mut()) {
+            contract.implied_volatility = contract.strike / 1000.0;
+        }
+        chain
+    }
+
+    #[test]
+    fn test_atm_iv_picks_nearest_strike() {
+        let chain = iv_skewed_chain(401.0);
+
+        let atm_iv = chain.atm_iv().unwrap();
+        assert!((atm_iv - 0.400).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_atm_iv_empty_chain() {
+        let mut chain = iv_skewed_chain(400.0);
+        chain.calls.clear();
+        chain.puts.clear();
+
+        assert_eq!(chain.atm_iv(), None);
+    }
+
+    #[test]
+    fn test_is_valid_market() {
+        let chain = iv_skewed_chain(400.0);
+        let mut contract = chain.get_call(400.0).unwrap().clone();
+        assert!(contract.is_valid_market());
+
+        // Crossed market
+        contract.bid = 2.10;
+        contract.ask = 2.00;
+        assert!(!contract.is_valid_market());
+
+        // Zero-priced market
+        contract.bid = 0.0;
+        contract.ask = 0.0;
+        assert!(!contract.is_valid_market());
+
+        // Non-finite quote
+        contract.bid = 1.0;
+        contract.ask = f64::NAN;
+        assert!(!contract.is_valid_market());
+    }
+
+    #[test]
+    fn test_strike_increment() {
+        let chain = iv_skewed_chain(400.0);
+        assert_eq!(chain.strike_increment(), Some(5.0));
+
+        let mut single = chain.clone();
+        single.calls.retain(|_, contract| contract.strike == 400.0);
+        single.puts.retain(|_, contract| contract.strike == 400.0);
+        assert_eq!(single.strike_increment(), None);
+    }
+
+    #[test]
+    fn test_contracts_sorted_by_strike() {
+        let chain = iv_skewed_chain(400.0);
+
+        for sorted in [chain.calls_sorted(), chain.puts_sorted()] {
+            assert_eq!(sorted.len(), 5);
+            assert!(
+                sorted
+                    .windows(2)
+                    .all(|pair| pair[0].strike < pair[1].strike)
+            );
+        }
+        assert_eq!(chain.calls_sorted()[0].option_type, OptionType::Call);
+        assert_eq!(chain.puts_sorted()[0].option_type, OptionType::Put);
+    }
+
+    #[test]
+    fn test_mid_spread_and_spread_pct() {
+        let chain = iv_skewed_chain(400.0);
+        let mut contract = chain.get_call(400.0).unwrap().clone();
+        contract.bid = 1.90;
+        contract.ask = 2.10;
+
+        assert!((contract.mid() - 2.00).abs() < 1e-12);
+        assert!((contract.spread() - 0.20).abs() < 1e-12);
+        assert!((contract.spread_pct().unwrap() - 0.10).abs() < 1e-12);
+
+        // Crossed quotes never report a negative spread
+        contract.bid = 2.20;
+        assert_eq!(contract.spread(), 0.0);
+
+        // No meaningful percentage without a positive mid
+        contract.bid = 0.0;
+        contract.ask = 0.0;
+        assert_eq!(contract.spread_pct(), None);
+    }
+
+    #[test]
+    fn test_nearest_strikes() {
+        let mut chain = iv_skewed_chain(400.0);
+        chain.puts.remove(&OptionsChain::strike_key(390.0));
+
+        let (calls, puts) = chain.nearest_strikes(401.0, 3);
+        assert_eq!(calls, vec![400.0, 405.0, 395.0]);
+        assert_eq!(puts, vec![400.0, 405.0, 395.0]);
+
+        // Asking for more strikes than listed returns them all
+        let (calls, puts) = chain.nearest_strikes(392.0, 10);
+        assert_eq!(calls, vec![390.0, 395.0, 400.0, 405.0, 410.0]);
+        assert_eq!(puts, vec![395.0, 400.0, 405.0, 410.0]);
+    }
+
+    #[test]
+    fn test_spreads_widen_near_expiration() {
+        let mut five_dte = iv_skewed_chain(400.0);
+        five_dte.expiration = five_dte.timestamp + chrono::Duration::days(5);
+        let mut zero_dte = iv_skewed_chain(400.0);
+        zero_dte.expiration = zero_dte.timestamp + chrono::Duration::hours(1);
+        let quoted_spread = five_dte.get_call(405.0).unwrap().spread();
+
+        five_dte.widen_spreads_for_expiry();
+        zero_dte.widen_spreads_for_expiry();
+
+        let five_dte_call = five_dte.get_call(405.0).unwrap();
+        let zero_dte_call = zero_dte.get_call(405.0).unwrap();
+        assert!((five_dte_call.spread() - quoted_spread).abs() < 1e-12);
+        assert!(zero_dte_call.spread() > 3.0 * five_dte_call.spread());
+        // The mid is preserved
+        assert!((zero_dte_call.mid() - five_dte_call.mid()).abs() < 1e-12);
+
+        // Further from the money widens more
+        let atm = zero_dte.get_put(400.0).unwrap();
+        let otm = zero_dte.get_put(390.0).unwrap();
+        assert!(otm.spread_pct().unwrap() / atm.spread_pct().unwrap() > 1.0);
+    }
+
+    /// Chain generated by `OptionsContract::new` over `strikes`
+    fn generate_chain(underlying_price: f64, strikes: impl Iterator<Item = u32>) -> OptionsChain {
+        strike_chain(underlying_price, Utc::now(), 1, strikes.map(f64::from))
+    }
+
+    #[test]
+    fn test_generated_chains_are_arbitrage_free() {
+        for spot_cents in (35_000..=45_000).step_by(37) {
+            let spot = spot_cents as f64 / 100.0;
+            let chain = generate_chain(spot, (300..=500).step_by(5));
+            assert_eq!(chain.check_arbitrage(), Vec::new(), "spot {}", spot);
+        }
+
+        // Low-priced underlyings, where the flat time value used to exceed the strike
+        for spot_cents in (500..=3_000).step_by(13) {
+            let spot = spot_cents as f64 / 100.0;
+            let chain = generate_chain(spot, 1..=40);
+            assert_eq!(chain.check_arbitrage(), Vec::new(), "spot {}", spot);
+        }
+    }
+
+    #[test]
+    fn test_check_arbitrage_flags_violations() {
+        let mut chain = iv_skewed_chain(400.0);
+        // 405 call priced above the 400 call, and far above the 410 call
+        let call = chain.get_call_mut(405.0).unwrap();
+        call.bid = 9.90;
+        call.ask = 10.10;
+        // 390 put worth more than its strike
+        let put = chain.get_put_mut(390.0).unwrap();
+        put.bid = 395.0;
+        put.ask = 396.0;
+
+        let violations = chain.check_arbitrage();
+        assert!(violations.contains(&ArbitrageViolation::CallNotMonotonic {
+            lower_strike: 400.0,
+            higher_strike: 405.0,
+        }));
+        assert!(
+            violations.contains(&ArbitrageViolation::SpreadWiderThanStrikes {
+                option_type: OptionType::Call,
+                lower_strike: 405.0,
+                higher_strike: 410.0,
+            })
+        );
+        assert!(violations.contains(&ArbitrageViolation::AboveUpperBound {
+            option_type: OptionType::Put,
+            strike: 390.0,
+        }));
+        assert!(!violations.contains(&ArbitrageViolation::BelowIntrinsic {
+            option_type: OptionType::Call,
+            strike: 405.0,
+        }));
+    }
+
+    #[test]
+    fn test_mutable_accessors_edit_quotes_in_place() {
+        let mut chain = iv_skewed_chain(400.0);
+
+        chain.get_call_mut(405.0).unwrap().bid = 1.23;
+        chain.get_put_mut(395.0).unwrap().bid = 0.45;
+
+        assert_eq!(chain.get_call(405.0).unwrap().bid, 1.23);
+        assert_eq!(chain.get_put(395.0).unwrap().bid, 0.45);
+        assert!(chain.get_call_mut(1000.0).is_none());
+    }
+
+    #[test]
+    fn test_merge_combines_greeks_and_quotes() {
+        // One source quotes the chain without Greeks, the other models Greeks without quotes
+        let quotes = iv_skewed_chain(400.0);
+        assert!(!quotes.has_greeks());
+        let mut greeks = quotes.clone();
+        for contract in greeks.calls.values_mut().chain(greeks.puts.values_mut()) {
+            contract.bid = 0.0;
+            contract.ask = 0.0;
+            contract.greeks.delta = 1.0 - contract.strike / 800.0;
+        }
+
+        for strategy in [
+            MergeStrategy::PreferNonZeroGreeks,
+            MergeStrategy::PreferTighterSpread,
+        ] {
+            let mut merged = quotes.clone();
+            merged.merge(&greeks, strategy).unwrap();
+            for (strike, contract) in &merged.calls {
+                assert_eq!(contract.bid, quotes.calls[strike].bid);
+                assert_eq!(contract.ask, quotes.calls[strike].ask);
+                assert_eq!(contract.greeks.delta, greeks.calls[strike].greeks.delta);
+            }
+            assert!(merged.has_greeks());
+        }
+
+        // Only a tighter spread replaces quotes that are already there
+        let mut tighter = quotes.clone();
+        tighter.get_call_mut(400.0).unwrap().widen_spread(0.5);
+        let mut merged = quotes.clone();
+        merged
+            .merge(&tighter, MergeStrategy::PreferNonZeroGreeks)
+            .unwrap();
+        assert_eq!(
+            merged.get_call(400.0).unwrap().ask,
+            quotes.get_call(400.0).unwrap().ask
+        );
+        merged
+            .merge(&tighter, MergeStrategy::PreferTighterSpread)
+            .unwrap();
+        assert_eq!(
+            merged.get_call(400.0).unwrap().ask,
+            tighter.get_call(400.0).unwrap().ask
+        );
+
+        greeks.expiration += chrono::Duration::days(1);
+        assert!(matches!(
+            merged.merge(&greeks, MergeStrategy::PreferNonZeroGreeks),
+            Err(MergeError::ExpirationMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn test_synthetic_time_value_scales_with_sqrt_dte() {
+        let now = Utc::now();
+        let atm_call = |days| {
+            OptionsContract::new(OptionsContractParams {
+                underlying: "SPY".to_string(),
+                option_type: OptionType::Call,
+                strike: 400.0,
+                expiration: now + chrono::Duration::days(days),
+                underlying_price: 400.0,
+                implied_vol: 0.20,
+                risk_free_rate: 0.05,
+                current_time: now,
+            })
+        };
+
+        let monthly = atm_call(30);
+        let daily = atm_call(1);
+        assert!(monthly.last_price > daily.last_price);
+        assert!((monthly.last_price / daily.last_price - 30.0_f64.sqrt()).abs() < 1e-9);
     }
 }
@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::math::{norm_cdf, norm_pdf};
+use crate::models::options_data::{OptionType, OptionsContract};
+
+/// Risk-free rate used when repricing contracts that don't carry their own
+pub const DEFAULT_RISK_FREE_RATE: f64 = 0.05;
+
+/// How open positions are valued each event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkSource {
+    /// Midpoint of the contract's bid and ask
+    Mid,
+    /// Contract's last traded price
+    Last,
+    /// Black-Scholes price from the contract's implied volatility
+    Theoretical,
+}
+
+impl MarkSource {
+    /// Per-share value of `contract` with the underlying at `spot`
+    pub fn mark(&self, contract: &OptionsContract, spot: f64, now: DateTime<Utc>) -> f64 {
+        match self {
+            MarkSource::Mid => contract.mid(),
+            MarkSource::Last => contract.last_price,
+            MarkSource::Theoretical => {
+                let years = (contract.expiration - now).num_seconds() as f64 / (365.0 * 86_400.0);
+                black_scholes_price(
+                    contract.option_type,
+                    spot,
+                    contract.strike,
+                    years,
+                    DEFAULT_RISK_FREE_RATE,
+                    contract.implied_volatility,
+                )
+            }
+        }
+    }
+}
+
+/// Black-Scholes price of a European option. Expired (or zero-vol) options are worth intrinsic.
+pub fn black_scholes_price(
+    option_type: OptionType,
+    spot: f64,
+    strike: f64,
+    years: f64,
+    rate: f64,
+    vol: f64,
+) -> f64 {
+    if years <= 0.0 || vol <= 0.0 {
+        return option_type.intrinsic_value(strike, spot);
+    }
+
+    let sqrt_t = years.sqrt();
+    let d1 = ((spot / strike).ln() + (rate + 0.5 * vol * vol) * years) / (vol * sqrt_t);
+    let d2 = d1 - vol * sqrt_t;
+    let discount = (-rate * years).exp();
+
+    match option_type {
+        OptionType::Call => spot * norm_cdf(d1) - strike * discount * norm_cdf(d2),
+        OptionType::Put => strike * discount * norm_cdf(-d2) - spot * norm_cdf(-d1),
+    }
+}
+
+/// Volatility bracket the implied-volatility solver searches
+const IV_SEARCH_RANGE: (f64, f64) = (1e-4, 5.0);
+
+/// Volatility at which the Black-Scholes price of the option matches `price`, with `dte`
+/// in (fractional) days. Newton-Raphson from 20%, falling back to bisection whenever a
+/// step would leave the bracket known to hold the root. None for expired options and
+/// prices outside what any volatility in the search range can produce.
+pub fn implied_volatility_from_price(
+    price: f64,
+    spot: f64,
+    strike: f64,
+    rate: f64,
+    dte: f64,
+    option_type: OptionType,
+) -> Option<f64> {
+    const MAX_ITERATIONS: usize = 100;
+    const PRICE_TOLERANCE: f64 = 1e-10;
+
+    let years = dte / 365.0;
+    if years <= 0.0 || spot <= 0.0 || strike <= 0.0 {
+        return None;
+    }
+    let price_at = |vol| black_scholes_price(option_type, spot, strike, years, rate, vol);
+
+    let (mut low, mut high) = IV_SEARCH_RANGE;
+    if !(price_at(low)..=price_at(high)).contains(&price) {
+        return None;
+    }
+
+    let sqrt_t = years.sqrt();
+    let mut vol = 0.20;
+    for _ in 0..MAX_ITERATIONS {
+        let error = price_at(vol) - price;
+        if error.abs() < PRICE_TOLERANCE {
+            return Some(vol);
+        }
+        // Price rises with volatility, so the sign of the error halves the bracket
+        if error > 0.0 {
+            high = vol;
+        } else {
+            low = vol;
+        }
+
+        let d1 = ((spot / strike).ln() + (rate + 0.5 * vol * vol) * years) / (vol * sqrt_t);
+        let vega = spot * sqrt_t * norm_pdf(d1);
+        let newton = vol - error / vega;
+        vol = if vega > f64::EPSILON && newton > low && newton < high {
+            newton
+        } else {
+            0.5 * (low + high)
+        };
+    }
+    Some(vol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::options_data::OptionsContractParams;
+
+    #[test]
+    fn test_black_scholes_reference_values() {
+        // Hull's textbook example: S=K=100, T=1, r=5%, vol=20%
+        let call = black_scholes_price(OptionType::Call, 100.0, 100.0, 1.0, 0.05, 0.20);
+        let put = black_scholes_price(OptionType::Put, 100.0, 100.0, 1.0, 0.05, 0.20);
+        assert!((call - 10.4506).abs() < 1e-3);
+        assert!((put - 5.5735).abs() < 1e-3);
+
+        // Expired options are worth intrinsic
+        let expired = black_scholes_price(OptionType::Put, 95.0, 100.0, 0.0, 0.05, 0.20);
+        assert_eq!(expired, 5.0);
+    }
+
+    #[test]
+    fn test_implied_volatility_recovers_black_scholes_input() {
+        for option_type in [OptionType::Call, OptionType::Put] {
+            for strike in [95.0, 100.0, 105.0] {
+                for (vol, dte) in [(0.12, 7.0), (0.20, 30.0), (0.65, 365.0)] {
+                    let price =
+                        black_scholes_price(option_type, 100.0, strike, dte / 365.0, 0.05, vol);
+                    let solved =
+                        implied_volatility_from_price(price, 100.0, strike, 0.05, dte, option_type)
+                            .unwrap();
+                    assert!(
+                        (solved - vol).abs() < 1e-4,
+                        "{option_type:?} {strike} {dte}d: {solved} vs {vol}"
+                    );
+                }
+            }
+        }
+
+        // Below intrinsic no volatility reproduces the price
+        assert_eq!(
+            implied_volatility_from_price(15.0, 120.0, 100.0, 0.05, 30.0, OptionType::Call),
+            None
+        );
+    }
+
+    #[test]
+    fn test_mark_sources_on_known_contract() {
+        let now = Utc::now();
+        let mut contract = OptionsContract::new(OptionsContractParams {
+            underlying: "SPY".to_string(),
+            option_type: OptionType::Call,
+            strike: 100.0,
+            expiration: now + chrono::Duration::days(365),
+            underlying_price: 100.0,
+            implied_vol: 0.20,
+            risk_free_rate: DEFAULT_RISK_FREE_RATE,
+            current_time: now,
+        });
+        contract.bid = 10.00;
+        contract.ask = 10.40;
+        contract.last_price = 10.25;
+
+        assert!((MarkSource::Mid.mark(&contract, 100.0, now) - 10.20).abs() < 1e-12);
+        assert_eq!(MarkSource::Last.mark(&contract, 100.0, now), 10.25);
+        let theoretical = MarkSource::Theoretical.mark(&contract, 100.0, now);
+        assert!((theoretical - 10.4506).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// Contract specification for an optionable product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductConfig {
+    /// Time of day the options expire on their expiration date, in `timezone`
+    pub expiration_time: NaiveTime,
+    /// Exchange timezone the expiration time is quoted in
+    pub timezone: Tz,
+    /// How expiring options settle
+    #[serde(default)]
+    pub settlement: SettlementType,
+}
+
+/// Settlement style of a product's options
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SettlementType {
+    /// Settles into the underlying: in-the-money legs are assigned or exercised
+    #[default]
+    Physical,
+    /// Settles in cash against the opening price of the expiration day (e.g. monthly SPX)
+    CashAmSettled,
+    /// Settles in cash against the closing price of the expiration day (e.g. SPXW)
+    #[allow(dead_code)]
+    CashPmSettled,
+}
+
+impl SettlementType {
+    /// Cash-settled options are never assigned: they pay out their intrinsic value
+    pub fn is_cash_settled(self) -> bool {
+        matches!(
+            self,
+            SettlementType::CashAmSettled | SettlementType::CashPmSettled
+        )
+    }
+}
+
+impl Default for ProductConfig {
+    fn default() -> Self {
+        Self {
+            expiration_time: NaiveTime::from_hms_opt(16, 0, 0).expect("valid time"),
+            timezone: chrono_tz::America::New_York,
+            settlement: SettlementType::Physical,
+        }
+    }
+}
+
+impl ProductConfig {
+    /// Cash-settled product whose options settle at the market open (e.g. monthly SPX)
+    #[allow(dead_code)]
+    pub fn am_settled() -> Self {
+        Self {
+            expiration_time: NaiveTime::from_hms_opt(9, 30, 0).expect("valid time"),
+            settlement: SettlementType::CashAmSettled,
+            ..Default::default()
+        }
+    }
+
+    /// UTC instant at which options expiring on `date` expire
+    pub fn expiration_utc(&self, date: NaiveDate) -> Option<DateTime<Utc>> {
+        self.timezone
+            .from_local_datetime(&date.and_time(self.expiration_time))
+            .earliest()
+            .map(|expiration| expiration.with_timezone(&Utc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiration_utc_accounts_for_dst() {
+        let product = ProductConfig::default();
+
+        // Winter: 16:00 EST is 21:00 UTC
+        let winter = NaiveDate::from_ymd_opt(2025, 1, 17).unwrap();
+        assert_eq!(
+            product.expiration_utc(winter).unwrap(),
+            Utc.with_ymd_and_hms(2025, 1, 17, 21, 0, 0).unwrap()
+        );
+
+        // Summer: 16:00 EDT is 20:00 UTC
+        let summer = NaiveDate::from_ymd_opt(2025, 7, 18).unwrap();
+        assert_eq!(
+            product.expiration_utc(summer).unwrap(),
+            Utc.with_ymd_and_hms(2025, 7, 18, 20, 0, 0).unwrap()
+        );
+    }
+}
@@ -1,31 +1,114 @@
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, Utc, Weekday};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tracing::info;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::models::iron_condor_position::IronCondorPosition;
-use crate::models::options_data::{OptionType, OptionsChain};
+use crate::models::iron_condor_position::{IronCondorPosition, Leg, LegFill, net_premium};
+use crate::models::options_data::{OptionType, OptionsChain, OptionsContract};
+use crate::models::pricing::MarkSource;
 
 /// Configuration for Iron Condor strategy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IronCondorConfig {
     /// Underlying symbol
     pub symbol: String,
-    /// Days to expiration (DTE) threshold
+    /// Days to expiration (DTE) non-0DTE entries look for
     pub dte_threshold: u32,
+    /// Non-0DTE entries need the chain's DTE within this many days of `dte_threshold`
+    pub dte_threshold_band: u32,
     /// Width between strikes as a percentage of underlying price
     pub width_percentage: f64,
-    /// Delta target for short strikes
+    /// Delta target for short strikes (used by `StrikeSelection::Delta`)
     pub delta_target: f64,
-    /// Exit profit percentage target
-    pub profit_target_pct: f64,
+    /// How the short strikes are picked
+    pub strike_selection: StrikeSelection,
+    /// Exit profit target
+    pub profit_target: ProfitTarget,
     /// Stop loss percentage
     pub stop_loss_pct: f64,
     /// Days to expiration to close position (0 for hold till expiration)
     pub exit_dte: u32,
     /// 0DTE strateg Y/N
     pub zero_dte: bool,
+    /// Never hold a position at or below this DTE to avoid pin/gamma risk (ignored for 0DTE)
+    pub min_hold_dte: Option<u32>,
+    /// EMA smoothing factor (0-1) for the price used in entry strike selection (None = spot)
+    pub price_smoothing_alpha: Option<f64>,
+    /// Strikes within this distance of the closest one are near-ties, broken by open interest
+    pub oi_tie_tolerance: f64,
+    /// Profit (as a fraction of max profit) a position must reach before the trailing stop arms
+    pub trailing_stop_activation_pct: f64,
+    /// Exit once this fraction of the peak profit has been given back (None = no trailing stop)
+    pub trailing_stop_pct: Option<f64>,
+    /// Exit when either short leg's absolute delta exceeds this (None = no delta stop)
+    pub delta_stop: Option<f64>,
+    /// How open positions are valued (None = intrinsic value at the mark price)
+    pub mark_source: Option<MarkSource>,
+    /// Preferred DTE of the traded expiration (None = nearest expiration)
+    pub target_dte: Option<u32>,
+    /// Skip entries when no expiration is within this many days of `target_dte`
+    pub max_dte_deviation: u32,
+    /// Distance between the short call and short put as a fraction of the underlying
+    /// price, centered on it (0 = iron fly with both shorts at the ATM strike)
+    pub body_width: f64,
+    /// Only consider this many listed strikes on each side of spot (None = whole chain)
+    pub strike_search_window: Option<usize>,
+    /// Skip entries that would push the book's absolute net vega above this (None = no limit)
+    pub max_net_vega: Option<f64>,
+    /// Skip entries that would push the book's absolute net gamma above this (None = no limit)
+    pub max_net_gamma: Option<f64>,
+    /// Scale a `PctOfMax` profit target by the entry IV rank: x0.5 at rank 0, x1.5 at rank 1
+    pub iv_rank_profit_scaling: bool,
+    /// Number of recent ATM IV observations (one per chain seen) the IV rank is computed
+    /// over. The rank stays None, and the IV rank rules off, until the window is full.
+    pub iv_rank_window: usize,
+    /// Skip entries where either short leg's bid carries less time value than this
+    pub min_short_extrinsic: Option<f64>,
+    /// Listed strike increment of the product (e.g. 2.5). None detects it from the chain,
+    /// which a stray off-increment strike in the data can throw off.
+    pub strike_increment: Option<f64>,
+    /// No new entries while the chain timestamp falls in any of these inclusive
+    /// (start, end) windows, e.g. around FOMC, CPI or earnings
+    pub blackout_windows: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Only enter on these weekdays of the chain timestamp (UTC), e.g. the days a product
+    /// has same-day expirations. Empty = every day.
+    pub entry_weekdays: Vec<Weekday>,
+    /// Contracts per position
+    pub quantity: u32,
+    /// Trade at most this fraction of the thinnest leg's volume per event (None = no cap).
+    /// Positions entered short of `quantity` are topped up on later events.
+    pub max_volume_participation: Option<f64>,
+    /// Skip entries whose short strikes sit further than this fraction of spot out of the
+    /// money, e.g. a delta target landing in the near-zero-credit tail (None = no limit)
+    pub max_otm_pct: Option<f64>,
+    /// No new entries for this long after a stop-loss exit, so a trending market isn't
+    /// re-entered straight away (zero = no cooldown)
+    pub cooldown_after_stop: Duration,
+    /// Force an exit once a position has been open longer than this, measured from entry
+    /// to the chain timestamp, for intraday books (None = no limit)
+    pub max_hold: Option<Duration>,
+}
+
+/// How the short strikes of a new position are picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub enum StrikeSelection {
+    /// Half the body width above and below the reference price
+    Percentage,
+    /// Absolute delta closest to `delta_target`. Chains without Greeks fall back to
+    /// percentage targeting.
+    Delta,
+}
+
+/// When to take profits on an open position
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProfitTarget {
+    /// Fraction of max profit (e.g. 0.50 closes at half the credit)
+    PctOfMax(f64),
+    /// Fixed dollar profit per contract
+    Dollars(f64),
 }
 
 impl Default for IronCondorConfig {
@@ -33,12 +116,38 @@ impl Default for IronCondorConfig {
         Self {
             symbol: "SPY".to_string(),
             dte_threshold: 7,
+            dte_threshold_band: 3,
             width_percentage: 0.05,
             delta_target: 0.16,
-            profit_target_pct: 0.50,
+            strike_selection: StrikeSelection::Percentage,
+            profit_target: ProfitTarget::PctOfMax(0.50),
             stop_loss_pct: 0.75,
             exit_dte: 0,
             zero_dte: true,
+            min_hold_dte: None,
+            price_smoothing_alpha: None,
+            oi_tie_tolerance: 0.0,
+            trailing_stop_activation_pct: 0.50,
+            trailing_stop_pct: None,
+            delta_stop: None,
+            mark_source: None,
+            target_dte: None,
+            max_dte_deviation: 5,
+            body_width: 0.10,
+            strike_search_window: None,
+            max_net_vega: None,
+            max_net_gamma: None,
+            iv_rank_profit_scaling: false,
+            iv_rank_window: 252,
+            min_short_extrinsic: None,
+            strike_increment: None,
+            blackout_windows: Vec::new(),
+            entry_weekdays: Vec::new(),
+            quantity: 1,
+            max_volume_participation: None,
+            max_otm_pct: None,
+            cooldown_after_stop: Duration::zero(),
+            max_hold: None,
         }
     }
 }
@@ -52,6 +161,14 @@ pub struct IronCondorState {
     pub current_price: f64,
     /// Last signal timestamp
     pub last_signal: Option<chrono::DateTime<Utc>>,
+    /// Exponential moving average of the underlying price (when smoothing is enabled)
+    pub price_ema: Option<f64>,
+    /// Highest profit percentage seen per open position, for the trailing stop
+    pub peak_profit_pct: HashMap<Uuid, f64>,
+    /// Recent ATM implied volatilities, oldest first, for the IV rank
+    pub iv_history: VecDeque<f64>,
+    /// When the most recent stop-loss exit happened, for the re-entry cooldown
+    pub last_stop_loss: Option<DateTime<Utc>>,
 }
 
 impl Default for IronCondorState {
@@ -60,10 +177,59 @@ impl Default for IronCondorState {
             active_positions: HashMap::new(),
             current_price: 0.0,
             last_signal: None,
+            price_ema: None,
+            peak_profit_pct: HashMap::new(),
+            iv_history: VecDeque::new(),
+            last_stop_loss: None,
         }
     }
 }
 
+/// Why a position was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExitReason {
+    ProfitTarget,
+    /// Trailing stop: part of the peak profit was given back before the fixed target
+    ProfitTargetPartial,
+    StopLoss,
+    /// A short leg's delta breached the configured threshold
+    DeltaStop,
+    DteExit,
+    MinHoldDte,
+    /// Held longer than the configured maximum holding period
+    MaxHold,
+    TimeExit,
+    /// Force-closed at the last mark when the backtest ran out of data
+    EndOfBacktest,
+    /// Liquidated because losses left too little equity to cover the margin held
+    MarginCall,
+    /// Closed with the rest of the book once the day's portfolio P&L hit its target
+    PortfolioProfitTarget,
+    /// Settled at intrinsic value once expiration passed: in-the-money legs were
+    /// assigned or exercised
+    Expiration,
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            ExitReason::ProfitTarget => "profit target",
+            ExitReason::ProfitTargetPartial => "trailing profit stop",
+            ExitReason::StopLoss => "stop loss",
+            ExitReason::DeltaStop => "delta stop",
+            ExitReason::DteExit => "DTE exit",
+            ExitReason::MinHoldDte => "min hold DTE exit",
+            ExitReason::MaxHold => "max hold",
+            ExitReason::TimeExit => "time exit",
+            ExitReason::EndOfBacktest => "end of backtest",
+            ExitReason::MarginCall => "margin call",
+            ExitReason::PortfolioProfitTarget => "portfolio profit target",
+            ExitReason::Expiration => "expiration",
+        };
+        f.write_str(reason)
+    }
+}
+
 /// Trading signal for Iron Condor strategy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum IronCondorSignal {
@@ -76,8 +242,20 @@ pub enum IronCondorSignal {
     Exit {
         position_id: Uuid,
         exit_premium: f64,
+        /// Per-leg fills used to close the position
+        fills: Vec<LegFill>,
+        timestamp: chrono::DateTime<Utc>,
+        reason: ExitReason,
+    },
+    /// Add contracts to an open position that was entered short of its target quantity
+    Adjust {
+        position_id: Uuid,
+        added_quantity: u32,
+        /// Net credit for the added contracts
+        added_premium: f64,
+        /// Per-leg fills for the added contracts
+        fills: Vec<LegFill>,
         timestamp: chrono::DateTime<Utc>,
-        reason: String,
     },
 }
 
@@ -96,13 +274,114 @@ impl IronCondorSignalGenerator {
         }
     }
 
-    /// Generate a trading signal based on current options chain data
+    /// Drop all positions and history so the generator can start a fresh run
+    pub fn reset(&mut self) {
+        self.state = IronCondorState::default();
+    }
+
+    /// Forget an entry whose order was never filled. Its signal no longer counts
+    /// towards the re-entry spacing, so the next chain may enter right away.
+    pub fn cancel_entry(&mut self, position_id: &Uuid) {
+        self.state.active_positions.remove(position_id);
+        self.state.peak_profit_pct.remove(position_id);
+        self.state.last_signal = None;
+    }
+
+    /// Where the latest ATM IV sits between the lowest (0) and highest (1) IV of the
+    /// lookback window. None while the window is still filling up or holds a single
+    /// distinct value.
+    pub fn iv_rank(&self) -> Option<f64> {
+        if self.state.iv_history.len() < self.config.iv_rank_window {
+            return None;
+        }
+        let current = *self.state.iv_history.back()?;
+        let (low, high) = self
+            .state
+            .iv_history
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &iv| {
+                (low.min(iv), high.max(iv))
+            });
+        (high > low).then(|| (current - low) / (high - low))
+    }
+
+    /// True if the book's net vega and gamma stay within the configured limits with
+    /// `candidate` added to the open positions
+    fn within_greek_limits(&self, candidate: &IronCondorPosition) -> bool {
+        let (mut vega, mut gamma) = (0.0, 0.0);
+        for position in self.state.active_positions.values().chain([candidate]) {
+            let greeks = position.net_greeks();
+            vega += greeks.vega;
+            gamma += greeks.gamma;
+        }
+
+        for (name, value, limit) in [
+            ("vega", vega, self.config.max_net_vega),
+            ("gamma", gamma, self.config.max_net_gamma),
+        ] {
+            if let Some(limit) = limit
+                && value.abs() > limit
+            {
+                info!(
+                    "No entry: net {} {:.4} would exceed the {:.4} limit",
+                    name, value, limit
+                );
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Stop tracking a position that was closed outside the strategy's own exit rules
+    pub fn take_position(&mut self, position_id: &Uuid) -> Option<IronCondorPosition> {
+        self.state.peak_profit_pct.remove(position_id);
+        self.state.active_positions.remove(position_id)
+    }
+
+    /// Generate a trading signal based on current options chain data,
+    /// marking open positions at the chain's own underlying price
+    #[allow(dead_code)]
     pub fn generate_signal_with_options_chain(
         &mut self,
         options_chain: &OptionsChain,
     ) -> Option<IronCondorSignal> {
+        self.generate_signal_with_mark_price(options_chain, options_chain.underlying_price)
+    }
+
+    /// Generate a trading signal based on current options chain data, marking open
+    /// positions at `mark_price` (the authoritative underlying price of the event)
+    pub fn generate_signal_with_mark_price(
+        &mut self,
+        options_chain: &OptionsChain,
+        mark_price: f64,
+    ) -> Option<IronCondorSignal> {
+        // Positions would be tagged with the wrong underlying
+        if !options_chain
+            .underlying
+            .eq_ignore_ascii_case(&self.config.symbol)
+        {
+            warn!(
+                "Ignoring {} options chain: strategy is configured for {}",
+                options_chain.underlying, self.config.symbol
+            );
+            return None;
+        }
+
         // Update current price
-        self.state.current_price = options_chain.underlying_price;
+        self.state.current_price = mark_price;
+        if let Some(alpha) = self.config.price_smoothing_alpha {
+            self.state.price_ema = Some(match self.state.price_ema {
+                Some(ema) => alpha * mark_price + (1.0 - alpha) * ema,
+                None => mark_price,
+            });
+        }
+
+        if let Some(atm_iv) = options_chain.atm_iv() {
+            self.state.iv_history.push_back(atm_iv);
+            while self.state.iv_history.len() > self.config.iv_rank_window.max(1) {
+                self.state.iv_history.pop_front();
+            }
+        }
 
         // Get current timestamp from options chain
         let current_time = options_chain.timestamp;
@@ -125,11 +404,71 @@ impl IronCondorSignalGenerator {
                     None => true,
                 };
                 let no_active_positions = self.state.active_positions.is_empty();
-                no_recent_signal && no_active_positions
+                let chain_dte = (options_chain.expiration - current_time).num_days();
+                let above_min_hold_dte = match self.config.min_hold_dte {
+                    Some(min_dte) => chain_dte > min_dte as i64,
+                    None => true,
+                };
+                let near_dte_threshold = (chain_dte - self.config.dte_threshold as i64).abs()
+                    <= self.config.dte_threshold_band as i64;
+                no_recent_signal && no_active_positions && above_min_hold_dte && near_dte_threshold
+            }
+        };
+
+        // Never silently trade an expiration far from the configured target
+        let within_target_dte = match self.config.target_dte {
+            Some(target) => {
+                let chain_dte = (options_chain.expiration - current_time).num_days();
+                (chain_dte - target as i64).abs() <= self.config.max_dte_deviation as i64
             }
+            None => true,
         };
 
-        if should_enter {
+        if should_enter && !within_target_dte {
+            info!(
+                "No entry: expiration {} is not within {} days of the {} DTE target",
+                options_chain.expiration.format("%Y-%m-%d"),
+                self.config.max_dte_deviation,
+                self.config.target_dte.unwrap_or_default()
+            );
+        }
+
+        let in_blackout = self
+            .config
+            .blackout_windows
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&current_time));
+        if should_enter && in_blackout {
+            info!(
+                "No entry: {} is inside a blackout window",
+                current_time.format("%Y-%m-%d %H:%M")
+            );
+        }
+
+        let in_cooldown = self
+            .state
+            .last_stop_loss
+            .is_some_and(|stopped_at| current_time - stopped_at < self.config.cooldown_after_stop);
+        if should_enter && in_cooldown {
+            info!(
+                "No entry: cooling down after the stop loss at {}",
+                self.state
+                    .last_stop_loss
+                    .unwrap_or_default()
+                    .format("%Y-%m-%d %H:%M")
+            );
+        }
+
+        let off_weekday = !self.config.entry_weekdays.is_empty()
+            && !self.config.entry_weekdays.contains(&current_time.weekday());
+        if should_enter && off_weekday {
+            info!(
+                "No entry: {} is not a configured entry weekday",
+                current_time.weekday()
+            );
+        }
+
+        if should_enter && within_target_dte && !in_blackout && !in_cooldown && !off_weekday {
             info!(
                 "Attempting to create iron condor position at {:.2} on {}",
                 options_chain.underlying_price,
@@ -142,7 +481,10 @@ impl IronCondorSignalGenerator {
             );
 
             // Try to create an iron condor using delta targeting
-            if let Some(position) = self.create_iron_condor_position(options_chain) {
+            if let Some(mut position) = self.create_iron_condor_position(options_chain)
+                && self.within_greek_limits(&position)
+            {
+                position.entry_iv_rank = self.iv_rank();
                 // Record entry
                 self.state
                     .active_positions
@@ -170,13 +512,45 @@ impl IronCondorSignalGenerator {
         let mut positions_to_exit = Vec::new();
 
         for position in self.state.active_positions.values() {
-            let current_pnl = position.calculate_pnl(options_chain.underlying_price);
-            let profit_pct = position.profit_percentage(options_chain.underlying_price);
+            let current_pnl = self
+                .config
+                .mark_source
+                .and_then(|source| {
+                    position.mark_pnl(options_chain, source, mark_price, current_time)
+                })
+                .unwrap_or_else(|| position.calculate_pnl(mark_price));
+            let profit_pct = position.pnl_percentage(current_pnl);
 
             // Exit conditions based on profit percentage
-            let profit_target_reached = profit_pct >= self.config.profit_target_pct * 100.0;
+            let profit_target_reached = match self.config.profit_target {
+                ProfitTarget::PctOfMax(pct) => {
+                    let pct = match position.entry_iv_rank {
+                        Some(rank) if self.config.iv_rank_profit_scaling => pct * (0.5 + rank),
+                        _ => pct,
+                    };
+                    profit_pct >= pct * 100.0
+                }
+                ProfitTarget::Dollars(dollars) => {
+                    current_pnl * 100.0 >= dollars * position.quantity as f64
+                }
+            };
             let stop_loss_reached = profit_pct <= -self.config.stop_loss_pct * 100.0;
 
+            // Trailing stop: once armed, exit when too much of the peak profit is given back
+            let peak_pct = self
+                .state
+                .peak_profit_pct
+                .entry(position.id)
+                .or_insert(profit_pct);
+            *peak_pct = peak_pct.max(profit_pct);
+            let trailing_stop_reached = match self.config.trailing_stop_pct {
+                Some(giveback) => {
+                    *peak_pct >= self.config.trailing_stop_activation_pct * 100.0
+                        && profit_pct <= *peak_pct * (1.0 - giveback)
+                }
+                None => false,
+            };
+
             // Time-based exit for testing
             let time_exit = match self.state.last_signal {
                 Some(last) => current_time - last > Duration::days(1), // Exit after 1 day for testing
@@ -186,77 +560,253 @@ impl IronCondorSignalGenerator {
             // DTE-based exit
             let dte_exit = position.days_to_expiration(current_time) <= self.config.exit_dte as i64;
 
-            if profit_target_reached || stop_loss_reached || time_exit || dte_exit {
+            // Close before the position crosses into pin risk territory
+            let min_hold_dte_exit = match self.config.min_hold_dte {
+                Some(min_dte) if !self.config.zero_dte => {
+                    position.days_to_expiration(current_time) <= min_dte as i64
+                }
+                _ => false,
+            };
+
+            let max_hold_exit = self
+                .config
+                .max_hold
+                .is_some_and(|max_hold| current_time - position.entry_time > max_hold);
+
+            // Short strike is being tested: use the current chain's deltas for our expiration
+            let delta_stop_reached = match self.config.delta_stop {
+                Some(threshold) if options_chain.expiration == position.short_call.expiration => {
+                    let breached = |contract: Option<&OptionsContract>| {
+                        contract.is_some_and(|c| c.greeks.delta.abs() > threshold)
+                    };
+                    breached(options_chain.get_call(position.short_call.strike))
+                        || breached(options_chain.get_put(position.short_put.strike))
+                }
+                _ => false,
+            };
+
+            if profit_target_reached
+                || trailing_stop_reached
+                || stop_loss_reached
+                || delta_stop_reached
+                || time_exit
+                || dte_exit
+                || min_hold_dte_exit
+                || max_hold_exit
+            {
                 let reason = if profit_target_reached {
-                    "profit target"
+                    ExitReason::ProfitTarget
+                } else if trailing_stop_reached {
+                    ExitReason::ProfitTargetPartial
                 } else if stop_loss_reached {
-                    "stop loss"
+                    ExitReason::StopLoss
+                } else if delta_stop_reached {
+                    ExitReason::DeltaStop
                 } else if dte_exit {
-                    "DTE exit"
+                    ExitReason::DteExit
+                } else if min_hold_dte_exit {
+                    ExitReason::MinHoldDte
+                } else if max_hold_exit {
+                    ExitReason::MaxHold
                 } else {
-                    "time exit"
+                    ExitReason::TimeExit
                 };
 
-                // Calculate exit premium
-                let exit_premium = (position.short_call.ask + position.short_put.ask
-                    - position.long_call.bid
-                    - position.long_put.bid)
-                    * position.quantity as f64;
+                // Calculate exit premium (net debit paid to close)
+                let fills = position.exit_fills();
+                let exit_premium = -fills.iter().map(LegFill::cash_flow).sum::<f64>();
 
                 info!(
                     "Iron Condor EXIT: {} at {:.2}, P&L=${:.2} ({:.1}%), reason={}",
                     position.summary(),
-                    options_chain.underlying_price,
+                    mark_price,
                     current_pnl,
                     profit_pct,
                     reason
                 );
 
-                positions_to_exit.push((position.id, exit_premium, reason.to_string()));
+                // How much of the P&L came from the underlying moving vs. time and vol
+                let exit_iv = options_chain
+                    .atm_iv()
+                    .unwrap_or(position.short_call.implied_volatility);
+                let attribution = position.pnl_attribution(mark_price, exit_iv, current_time);
+
+                positions_to_exit.push((position.id, exit_premium, fills, reason, attribution));
             }
         }
 
         // Return first exit signal (process one at a time)
-        if let Some((position_id, exit_premium, reason)) = positions_to_exit.first() {
+        if let Some((position_id, exit_premium, fills, reason, attribution)) =
+            positions_to_exit.into_iter().next()
+        {
+            info!(
+                %position_id,
+                %reason,
+                underlying_pnl = attribution.delta + attribution.gamma,
+                time_decay_pnl = attribution.theta,
+                vol_pnl = attribution.vega,
+                total_pnl = attribution.total,
+                "Exit P&L attribution"
+            );
+
             // Remove the position from active positions
-            self.state.active_positions.remove(position_id);
+            self.state.active_positions.remove(&position_id);
+            self.state.peak_profit_pct.remove(&position_id);
+            if reason == ExitReason::StopLoss {
+                self.state.last_stop_loss = Some(current_time);
+            }
 
             return Some(IronCondorSignal::Exit {
-                position_id: *position_id,
-                exit_premium: *exit_premium,
+                position_id,
+                exit_premium,
+                fills,
                 timestamp: current_time,
-                reason: reason.clone(),
+                reason,
             });
         }
 
-        None
+        self.top_up_position(options_chain)
+    }
+
+    /// Contracts the chain's volume allows trading across `contracts` this event
+    fn volume_capacity(&self, contracts: &[&OptionsContract]) -> u32 {
+        match self.config.max_volume_participation {
+            Some(participation) => contracts
+                .iter()
+                .map(|contract| (contract.volume as f64 * participation).floor() as u32)
+                .min()
+                .unwrap_or(0),
+            None => u32::MAX,
+        }
+    }
+
+    /// Add contracts to the first open position on this chain's expiration that is short
+    /// of the target quantity, as far as the legs' volume allows and while it's a credit
+    fn top_up_position(&mut self, options_chain: &OptionsChain) -> Option<IronCondorSignal> {
+        let target = self.config.quantity;
+        let (position_id, added_quantity, fills, added_premium) = self
+            .state
+            .active_positions
+            .values()
+            .filter(|position| {
+                position.quantity < target
+                    && position.short_call.expiration == options_chain.expiration
+            })
+            .find_map(|position| {
+                let current: Vec<&OptionsContract> = position
+                    .legs()
+                    .iter()
+                    .map(|leg| match leg.contract.option_type {
+                        OptionType::Call => options_chain.get_call(leg.contract.strike),
+                        OptionType::Put => options_chain.get_put(leg.contract.strike),
+                    })
+                    .collect::<Option<_>>()?;
+                let added = (target - position.quantity).min(self.volume_capacity(&current));
+                if added == 0 {
+                    return None;
+                }
+
+                let legs: Vec<Leg> = position
+                    .legs()
+                    .iter()
+                    .zip(current)
+                    .map(|(leg, contract)| Leg {
+                        contract,
+                        quantity: added,
+                        ..*leg
+                    })
+                    .collect();
+                let premium = net_premium(&legs);
+                (premium > 0.0).then(|| {
+                    (
+                        position.id,
+                        added,
+                        legs.iter().map(Leg::fill).collect::<Vec<_>>(),
+                        premium,
+                    )
+                })
+            })?;
+
+        let position = self.state.active_positions.get_mut(&position_id)?;
+        position.add_contracts(added_quantity, added_premium);
+        info!(
+            "Iron Condor TOP-UP: {} +{} contracts for ${:.2}, now {}/{}",
+            position_id, added_quantity, added_premium, position.quantity, target
+        );
+
+        Some(IronCondorSignal::Adjust {
+            position_id,
+            added_quantity,
+            added_premium,
+            fills,
+            timestamp: options_chain.timestamp,
+        })
     }
 
-    /// Create an iron condor position using delta targeting
+    /// Create an iron condor position using the configured strike selection
     fn create_iron_condor_position(
         &self,
         options_chain: &OptionsChain,
     ) -> Option<IronCondorPosition> {
         let underlying_price = options_chain.underlying_price;
+        // Smoothed price (if enabled) reduces whipsaw in strike selection
+        let reference_price = self.state.price_ema.unwrap_or(underlying_price);
+
+        // Targets and widths are expressed in multiples of the listed strike increment
+        let increment = self
+            .config
+            .strike_increment
+            .or_else(|| options_chain.strike_increment())
+            .unwrap_or(1.0);
+        let snap = |price: f64| round_to_increment(price, increment);
+
+        let (short_call_strike, short_put_strike) = match self.config.strike_selection {
+            StrikeSelection::Delta if options_chain.has_greeks() => {
+                info!(
+                    "Delta targeting: Looking for {:.2} delta short strikes",
+                    self.config.delta_target
+                );
+                (
+                    self.find_strike_by_delta(options_chain, OptionType::Call),
+                    self.find_strike_by_delta(options_chain, OptionType::Put),
+                )
+            }
+            selection => {
+                // Zero deltas everywhere would make every strike look equally far from the target
+                if selection == StrikeSelection::Delta {
+                    warn!(
+                        "{} chain expiring {} has no Greeks, falling back to percentage targeting",
+                        options_chain.underlying,
+                        options_chain.expiration.format("%Y-%m-%d")
+                    );
+                }
 
-        // Instead of delta targeting, let's use percentage-based strikes that are more likely to work
-        // Find strikes that are a reasonable distance from the current price
-        let call_distance = underlying_price * 0.05; // 5% above current price for short call
-        let put_distance = underlying_price * 0.05; // 5% below current price for short put
-
-        let target_short_call_strike = (underlying_price + call_distance).round();
-        let target_short_put_strike = (underlying_price - put_distance).round();
+                // Short strikes sit half the body width above and below the current price
+                let call_distance = reference_price * self.config.body_width / 2.0;
+                let put_distance = reference_price * self.config.body_width / 2.0;
+                let target_short_call_strike = snap(reference_price + call_distance);
+                let target_short_put_strike = snap(reference_price - put_distance);
 
-        // Find closest available strikes to our targets
-        let short_call_strike =
-            self.find_closest_strike(options_chain, target_short_call_strike, OptionType::Call);
-        let short_put_strike =
-            self.find_closest_strike(options_chain, target_short_put_strike, OptionType::Put);
+                info!(
+                    "Percentage targeting: Looking for call strike near {:.1}, put strike near {:.1}",
+                    target_short_call_strike, target_short_put_strike
+                );
 
-        info!(
-            "Percentage targeting: Looking for call strike near {:.1}, put strike near {:.1}",
-            target_short_call_strike, target_short_put_strike
-        );
+                // Find closest available strikes to our targets
+                (
+                    self.find_closest_strike(
+                        options_chain,
+                        target_short_call_strike,
+                        OptionType::Call,
+                    ),
+                    self.find_closest_strike(
+                        options_chain,
+                        target_short_put_strike,
+                        OptionType::Put,
+                    ),
+                )
+            }
+        };
 
         if let (Some(sc_strike), Some(sp_strike)) = (short_call_strike, short_put_strike) {
             info!(
@@ -264,8 +814,22 @@ impl IronCondorSignalGenerator {
                 sc_strike, sp_strike
             );
 
+            if let Some(max_otm_pct) = self.config.max_otm_pct {
+                let otm_pct =
+                    (sc_strike / underlying_price - 1.0).max(1.0 - sp_strike / underlying_price);
+                if otm_pct > max_otm_pct {
+                    info!(
+                        "Position rejected: short strikes reach {:.1}% OTM, beyond {:.1}%",
+                        otm_pct * 100.0,
+                        max_otm_pct * 100.0
+                    );
+                    return None;
+                }
+            }
+
             // Calculate protection strikes based on fixed dollar amounts, but ensure they exist
-            let protection_width = 10.0; // $10 wide spreads
+            // ~$10 wide spreads, but never narrower than one strike increment
+            let protection_width = snap(10.0).max(increment);
 
             let target_long_call_strike = sc_strike + protection_width;
             let target_long_put_strike = sp_strike - protection_width;
@@ -273,10 +837,10 @@ impl IronCondorSignalGenerator {
             // Find the closest available strikes for protection
             let long_call_strike = self
                 .find_closest_strike(options_chain, target_long_call_strike, OptionType::Call)
-                .unwrap_or(sc_strike + 5.0); // Fallback to smaller width
+                .unwrap_or(sc_strike + increment); // Fallback to smallest width
             let long_put_strike = self
                 .find_closest_strike(options_chain, target_long_put_strike, OptionType::Put)
-                .unwrap_or(sp_strike - 5.0); // Fallback to smaller width
+                .unwrap_or(sp_strike - increment); // Fallback to smallest width
 
             info!(
                 "Protection strikes: long call={:.1}, long put={:.1}",
@@ -294,6 +858,20 @@ impl IronCondorSignalGenerator {
             {
                 info!("All contracts found, creating iron condor position");
 
+                // Selling mostly intrinsic value is all risk and little decay
+                if let Some(min_extrinsic) = self.config.min_short_extrinsic {
+                    for short in [sc, sp] {
+                        let extrinsic = short.bid - short.intrinsic_value(underlying_price);
+                        if extrinsic < min_extrinsic {
+                            info!(
+                                "Position rejected: short {:?} {:.1} has ${:.2} extrinsic, below ${:.2}",
+                                short.option_type, short.strike, extrinsic, min_extrinsic
+                            );
+                            return None;
+                        }
+                    }
+                }
+
                 // Debug: Log the actual option prices
                 info!(
                     "Short call {:.1}: bid=${:.2}, ask=${:.2}, delta={:.3}",
@@ -312,64 +890,46 @@ impl IronCondorSignalGenerator {
                     lp.strike, lp.bid, lp.ask, lp.greeks.delta
                 );
 
+                // Thin markets only fill part of the target size now
+                let quantity = self
+                    .config
+                    .quantity
+                    .min(self.volume_capacity(&[sc, lc, sp, lp]));
+                if quantity == 0 {
+                    info!("Position rejected: leg volume too thin to fill a single contract");
+                    return None;
+                }
+
                 // Create the iron condor position
-                let position = IronCondorPosition::new(
+                // Only a net credit produces a valid position
+                match IronCondorPosition::new(
                     self.config.symbol.clone(),
                     sc.clone(),
                     lc.clone(),
                     sp.clone(),
                     lp.clone(),
-                    1, // quantity
+                    quantity,
                     options_chain.timestamp,
-                );
-
-                info!("Position premium: ${:.2}", position.entry_premium);
-
-                // Only create position if we receive a net credit
-                if position.entry_premium > 0.0 {
-                    Some(position)
-                } else {
-                    info!(
-                        "Position rejected: negative premium (${:.2})",
-                        position.entry_premium
-                    );
-                    None
+                    underlying_price,
+                ) {
+                    Ok(position) => {
+                        info!("Position premium: ${:.2}", position.entry_premium);
+                        Some(position)
+                    }
+                    Err(e) => {
+                        info!("Position rejected: {}", e);
+                        None
+                    }
                 }
             } else {
                 // Debug which strikes are available
-                let available_call_strikes: Vec<f64> =
-                    options_chain.calls.keys().map(|&k| k as f64).collect();
-                let available_put_strikes: Vec<f64> =
-                    options_chain.puts.keys().map(|&k| k as f64).collect();
-
-                let call_min = available_call_strikes
-                    .iter()
-                    .min_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(&0.0);
-                let call_max = available_call_strikes
-                    .iter()
-                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(&0.0);
-                let put_min = available_put_strikes
-                    .iter()
-                    .min_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(&0.0);
-                let put_max = available_put_strikes
-                    .iter()
-                    .max_by(|a, b| a.partial_cmp(b).unwrap())
-                    .unwrap_or(&0.0);
-
                 info!(
-                    "Available call strikes: {} (min: {:.0}, max: {:.0})",
-                    available_call_strikes.len(),
-                    call_min,
-                    call_max
+                    "Available call strikes: {}",
+                    describe_strikes(&options_chain.calls)
                 );
                 info!(
-                    "Available put strikes: {} (min: {:.0}, max: {:.0})",
-                    available_put_strikes.len(),
-                    put_min,
-                    put_max
+                    "Available put strikes: {}",
+                    describe_strikes(&options_chain.puts)
                 );
 
                 info!(
@@ -383,83 +943,157 @@ impl IronCondorSignalGenerator {
             }
         } else {
             info!(
-                "Could not find strikes for delta targeting: call={:?}, put={:?}",
+                "Could not find short strikes: call={:?}, put={:?}",
                 short_call_strike, short_put_strike
             );
             None
         }
     }
 
-    /// Find the closest available strike to a target strike
+    /// Out-of-the-money strike whose absolute delta is closest to the delta target.
+    /// Ties go to the strike further out of the money.
+    fn find_strike_by_delta(
+        &self,
+        options_chain: &OptionsChain,
+        option_type: OptionType,
+    ) -> Option<f64> {
+        let spot = options_chain.underlying_price;
+
+        self.strike_candidates(options_chain, option_type)
+            .into_iter()
+            .map(|(strike, contract)| (strike, contract.greeks.delta.abs()))
+            .filter(|(strike, _)| match option_type {
+                OptionType::Call => *strike >= spot,
+                OptionType::Put => *strike <= spot,
+            })
+            .min_by(|(a, a_delta), (b, b_delta)| {
+                let further_otm = match option_type {
+                    OptionType::Call => b.total_cmp(a),
+                    OptionType::Put => a.total_cmp(b),
+                };
+                (a_delta - self.config.delta_target)
+                    .abs()
+                    .total_cmp(&(b_delta - self.config.delta_target).abs())
+                    .then(further_otm)
+            })
+            .map(|(strike, _)| strike)
+    }
+
+    /// Find the closest available strike to a target strike. Exact ties go to the strike
+    /// further out of the money (higher for calls, lower for puts) so the pick never
+    /// depends on the chain's hash order.
     fn find_closest_strike(
         &self,
         options_chain: &OptionsChain,
         target_strike: f64,
         option_type: OptionType,
     ) -> Option<f64> {
+        let candidates: Vec<(f64, u32)> = self
+            .strike_candidates(options_chain, option_type)
+            .into_iter()
+            .map(|(strike, contract)| (strike, contract.open_interest))
+            .collect();
+
+        let closest_diff = candidates
+            .iter()
+            .map(|(strike, _)| (strike - target_strike).abs())
+            .min_by(|a, b| a.total_cmp(b))?;
+
+        // Among near-ties prefer the more liquid strike, then the closer one, then the
+        // one further out of the money
+        candidates
+            .into_iter()
+            .filter(|(strike, _)| {
+                (strike - target_strike).abs() <= closest_diff + self.config.oi_tie_tolerance
+            })
+            .max_by(|(a, a_oi), (b, b_oi)| {
+                let a_diff = (a - target_strike).abs();
+                let b_diff = (b - target_strike).abs();
+                let further_otm = match option_type {
+                    OptionType::Call => a.total_cmp(b),
+                    OptionType::Put => b.total_cmp(a),
+                };
+                a_oi.cmp(b_oi)
+                    .then_with(|| b_diff.total_cmp(&a_diff))
+                    .then(further_otm)
+            })
+            .map(|(strike, _)| strike)
+    }
+
+    /// Validly quoted strikes of one side of the chain, sorted by strike and limited to
+    /// `strike_search_window` strikes either side of spot
+    fn strike_candidates<'a>(
+        &self,
+        options_chain: &'a OptionsChain,
+        option_type: OptionType,
+    ) -> Vec<(f64, &'a OptionsContract)> {
         let contracts = match option_type {
             OptionType::Call => &options_chain.calls,
             OptionType::Put => &options_chain.puts,
         };
 
-        contracts.keys().map(|&k| k as f64).min_by(|&a, &b| {
-            let a_diff = (a - target_strike).abs();
-            let b_diff = (b - target_strike).abs();
-            a_diff.partial_cmp(&b_diff).unwrap()
-        })
+        let mut candidates: Vec<(f64, &OptionsContract)> = contracts
+            .values()
+            .filter(|contract| contract.is_valid_market())
+            .map(|contract| (contract.strike, contract))
+            .collect();
+        candidates.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        // Far-from-spot strikes are slow to scan and tend to carry bad quotes
+        if let Some(window) = self.config.strike_search_window {
+            let spot = options_chain.underlying_price;
+            let Some(atm_idx) = candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, (a, _)), (_, (b, _))| (a - spot).abs().total_cmp(&(b - spot).abs()))
+                .map(|(idx, _)| idx)
+            else {
+                return candidates;
+            };
+            let lowest = atm_idx.saturating_sub(window);
+            let highest = (atm_idx + window).min(candidates.len() - 1);
+            candidates = candidates[lowest..=highest].to_vec();
+        }
+
+        candidates
+    }
+}
+
+/// Nearest multiple of `increment` to `price`
+fn round_to_increment(price: f64, increment: f64) -> f64 {
+    (price / increment).round() * increment
+}
+
+/// Count and range of the listed strikes, for logging
+fn describe_strikes(contracts: &HashMap<u32, OptionsContract>) -> String {
+    let strikes = contracts.values().map(|contract| contract.strike);
+    match (
+        strikes.clone().min_by(f64::total_cmp),
+        strikes.max_by(f64::total_cmp),
+    ) {
+        (Some(min), Some(max)) => {
+            format!("{} (min: {}, max: {})", contracts.len(), min, max)
+        }
+        _ => "no strikes available".to_string(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::options_data::{
-        OptionType, OptionsChain, OptionsContract, OptionsContractParams,
-    };
-    use chrono::Utc;
-    use std::collections::HashMap;
+    use crate::clock::{Clock, FixedClock};
+    use crate::models::iron_condor_position::PositionError;
+    use crate::models::options_data::{OptionType, OptionsChain, OptionsContract};
+    use crate::test_fixtures::{create_test_chain, strike_chain};
+    use chrono::{TimeZone, Utc};
+
+    /// Fixed "now" of the test chains: a Monday during US market hours
+    fn test_clock() -> FixedClock {
+        FixedClock(Utc.with_ymd_and_hms(2025, 6, 2, 14, 30, 0).unwrap())
+    }
 
     fn create_test_options_chain() -> OptionsChain {
-        let mut calls = HashMap::new();
-        let mut puts = HashMap::new();
-        let underlying_price = 400.0;
-        let expiration = Utc::now() + chrono::Duration::days(30);
-        let current_time = Utc::now();
-
-        for strike in (370..=430).step_by(5) {
-            let call = OptionsContract::new(OptionsContractParams {
-                underlying: "SPY".to_string(),
-                option_type: OptionType::Call,
-                strike: strike as f64,
-                expiration,
-                underlying_price,
-                implied_vol: 0.20,
-                risk_free_rate: 0.05,
-                current_time,
-            });
-            calls.insert(strike, call);
-
-            let put = OptionsContract::new(OptionsContractParams {
-                underlying: "SPY".to_string(),
-                option_type: OptionType::Put,
-                strike: strike as f64,
-                expiration,
-                underlying_price,
-                implied_vol: 0.20,
-                risk_free_rate: 0.05,
-                current_time,
-            });
-            puts.insert(strike, put);
-        }
-
-        OptionsChain {
-            underlying: "SPY".to_string(),
-            expiration,
-            underlying_price,
-            calls,
-            puts,
-            timestamp: current_time,
-        }
+        create_test_chain(400.0, test_clock().now())
     }
 
     #[test]
@@ -469,10 +1103,11 @@ mod tests {
             dte_threshold: 1,
             width_percentage: 0.05,
             delta_target: 0.16,
-            profit_target_pct: 0.50,
+            profit_target: ProfitTarget::PctOfMax(0.50),
             stop_loss_pct: 0.75,
             exit_dte: 0,
             zero_dte: true,
+            ..Default::default()
         };
 
         let mut generator = IronCondorSignalGenerator::new(config);
@@ -526,6 +1161,9 @@ mod tests {
             Some(IronCondorSignal::Enter { .. }) => {
                 panic!("Should not generate duplicate entry signal")
             }
+            Some(IronCondorSignal::Adjust { .. }) => {
+                panic!("Should not top up a position already at full size")
+            }
         }
     }
 
@@ -533,8 +1171,8 @@ mod tests {
     fn test_iron_condor_exit_conditions() {
         let config = IronCondorConfig {
             symbol: "SPY".to_string(),
-            profit_target_pct: 0.25, // 25% profit target for testing
-            stop_loss_pct: 0.50,     // 50% stop loss
+            profit_target: ProfitTarget::PctOfMax(0.25), // 25% profit target for testing
+            stop_loss_pct: 0.50,                         // 50% stop loss
             zero_dte: true,
             ..Default::default()
         };
@@ -548,7 +1186,7 @@ mod tests {
 
         // Fun scenario where the position should hit profit target by moving the underlying price to make the iron condor profitable
         let mut profitable_chain = options_chain.clone();
-        profitable_chain.timestamp = Utc::now() + chrono::Duration::hours(1); // Small time advance
+        profitable_chain.timestamp = options_chain.timestamp + chrono::Duration::hours(1); // Small time advance
         // Keep the underlying price the same to maximize profit (iron condor profits when price stays between short strikes)
         profitable_chain.underlying_price = 400.0;
 
@@ -561,7 +1199,7 @@ mod tests {
 
         if let Some(IronCondorSignal::Exit { reason, .. }) = exit_signal {
             // Accept either profit target or time exit as valid reasons
-            assert!(reason == "profit target" || reason == "time exit");
+            assert!(reason == ExitReason::ProfitTarget || reason == ExitReason::TimeExit);
         }
     }
 
@@ -583,13 +1221,12 @@ mod tests {
         let exact_match = generator.find_closest_strike(&options_chain, 400.0, OptionType::Call);
         assert_eq!(exact_match, Some(400.0));
 
-        // Test equidistant case - should return one of the two closest strikes
+        // Equidistant cases go to the strike further out of the money
         let equidistant = generator.find_closest_strike(&options_chain, 402.5, OptionType::Call);
-        assert!(equidistant == Some(400.0) || equidistant == Some(405.0));
+        assert_eq!(equidistant, Some(405.0));
 
-        // Test another equidistant case
         let equidistant_put = generator.find_closest_strike(&options_chain, 397.5, OptionType::Put);
-        assert!(equidistant_put == Some(395.0) || equidistant_put == Some(400.0));
+        assert_eq!(equidistant_put, Some(395.0));
 
         // Test out of range - should return the furthest available strike (highest strike)
         let out_of_range = generator.find_closest_strike(&options_chain, 500.0, OptionType::Call);
@@ -618,7 +1255,7 @@ mod tests {
         };
         let mut generator = IronCondorSignalGenerator::new(config);
 
-        let base_time = Utc::now();
+        let base_time = test_clock().now();
         let mut options_chain = create_test_options_chain();
         options_chain.timestamp = base_time;
 
@@ -639,4 +1276,1135 @@ mod tests {
         let signal3 = generator.generate_signal_with_options_chain(&options_chain);
         assert!(signal3.is_some());
     }
+
+    #[test]
+    fn test_min_hold_dte_skips_short_dte_entry() {
+        let config = IronCondorConfig {
+            zero_dte: false,
+            min_hold_dte: Some(5),
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+
+        let mut options_chain = create_test_options_chain();
+        options_chain.expiration = options_chain.timestamp + chrono::Duration::days(2);
+
+        let signal = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(signal.is_none());
+        assert!(generator.state.active_positions.is_empty());
+    }
+
+    #[test]
+    fn test_non_zero_dte_entry_needs_chain_near_dte_threshold() {
+        let config = IronCondorConfig {
+            zero_dte: false,
+            dte_threshold: 7,
+            dte_threshold_band: 2,
+            ..Default::default()
+        };
+        let mut options_chain = create_test_options_chain();
+
+        // 30 DTE is far outside 7 +/- 2
+        let mut generator = IronCondorSignalGenerator::new(config.clone());
+        assert!(
+            generator
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+        assert!(generator.state.active_positions.is_empty());
+
+        options_chain.expiration = options_chain.timestamp + chrono::Duration::days(8);
+        let mut generator = IronCondorSignalGenerator::new(config);
+        let signal = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(matches!(signal, Some(IronCondorSignal::Enter { .. })));
+    }
+
+    #[test]
+    fn test_min_hold_dte_forces_closure() {
+        let config = IronCondorConfig {
+            zero_dte: false,
+            dte_threshold: 30,
+            min_hold_dte: Some(5),
+            profit_target: ProfitTarget::PctOfMax(10.0), // Out of reach so the DTE rule decides
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+
+        let mut options_chain = create_test_options_chain();
+        let entry_signal = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(matches!(entry_signal, Some(IronCondorSignal::Enter { .. })));
+
+        // 30 DTE chain advanced by 26 days leaves 4 DTE, below the minimum
+        options_chain.timestamp += chrono::Duration::days(26);
+        let exit_signal = generator.generate_signal_with_options_chain(&options_chain);
+
+        match exit_signal {
+            Some(IronCondorSignal::Exit { reason, .. }) => {
+                assert_eq!(reason, ExitReason::MinHoldDte)
+            }
+            _ => panic!("Expected min hold DTE exit"),
+        }
+    }
+
+    #[test]
+    fn test_exit_uses_mark_price_over_chain_price() {
+        let config = IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(10.0), // Out of reach so only the stop loss can fire
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+        let mut options_chain = create_test_options_chain();
+
+        let entry_signal = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(matches!(entry_signal, Some(IronCondorSignal::Enter { .. })));
+
+        // The chain still claims 400 but the authoritative mark is past the call wing
+        options_chain.timestamp += chrono::Duration::hours(1);
+        let exit_signal = generator.generate_signal_with_mark_price(&options_chain, 460.0);
+
+        match exit_signal {
+            Some(IronCondorSignal::Exit { reason, .. }) => assert_eq!(reason, ExitReason::StopLoss),
+            _ => panic!("Expected stop loss exit at the mark price"),
+        }
+        assert_eq!(generator.state.current_price, 460.0);
+    }
+
+    #[test]
+    fn test_wing_snapped_onto_short_strike_is_rejected() {
+        // Only two strikes: the $10 wings can only snap back onto the shorts
+        let mut options_chain = create_test_options_chain();
+        options_chain
+            .calls
+            .retain(|_, contract| [395.0, 405.0].contains(&contract.strike));
+        options_chain
+            .puts
+            .retain(|_, contract| [395.0, 405.0].contains(&contract.strike));
+
+        let mut generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        assert!(
+            generator
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+        assert!(generator.state.active_positions.is_empty());
+
+        let call = options_chain.get_call(405.0).unwrap().clone();
+        let put = options_chain.get_put(395.0).unwrap().clone();
+        let result = IronCondorPosition::new(
+            "SPY".to_string(),
+            call.clone(),
+            call,
+            put.clone(),
+            put,
+            1,
+            options_chain.timestamp,
+            400.0,
+        );
+        assert!(matches!(
+            result,
+            Err(PositionError::ZeroWidthSpread {
+                option_type: OptionType::Call,
+                strike: 405.0,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cooldown_after_stop_blocks_reentry() {
+        let config = IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(10.0), // Out of reach so only the stop loss can fire
+            cooldown_after_stop: chrono::Duration::days(10),
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+        let mut options_chain = create_test_options_chain();
+        let entry_time = options_chain.timestamp;
+
+        let entry_signal = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(matches!(entry_signal, Some(IronCondorSignal::Enter { .. })));
+
+        options_chain.timestamp = entry_time + chrono::Duration::hours(1);
+        let exit_signal = generator.generate_signal_with_mark_price(&options_chain, 460.0);
+        assert!(matches!(
+            exit_signal,
+            Some(IronCondorSignal::Exit {
+                reason: ExitReason::StopLoss,
+                ..
+            })
+        ));
+
+        // Past the weekly re-entry spacing but still inside the 10-day cooldown
+        options_chain.timestamp = entry_time + chrono::Duration::days(8);
+        assert!(
+            generator
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+
+        options_chain.timestamp = entry_time + chrono::Duration::days(11);
+        let reentry = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(matches!(reentry, Some(IronCondorSignal::Enter { .. })));
+    }
+
+    #[test]
+    fn test_max_hold_forces_exit_on_hourly_events() {
+        let config = IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(10.0), // Out of reach so only the max hold can fire
+            max_hold: Some(chrono::Duration::hours(3)),
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+        let mut options_chain = create_test_options_chain();
+        let entry_time = options_chain.timestamp;
+
+        let entry_signal = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(matches!(entry_signal, Some(IronCondorSignal::Enter { .. })));
+
+        // Exactly three hours open is not yet longer than the limit
+        for hour in 1..=3 {
+            options_chain.timestamp = entry_time + chrono::Duration::hours(hour);
+            assert!(
+                generator
+                    .generate_signal_with_options_chain(&options_chain)
+                    .is_none(),
+                "unexpected signal {}h after entry",
+                hour
+            );
+        }
+
+        options_chain.timestamp = entry_time + chrono::Duration::hours(4);
+        let exit_signal = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(matches!(
+            exit_signal,
+            Some(IronCondorSignal::Exit {
+                reason: ExitReason::MaxHold,
+                ..
+            })
+        ));
+    }
+
+    /// Log sink shared with the test that installed it
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Numeric value of `field=` on the first log line containing `message`
+    fn log_field(logs: &str, message: &str, field: &str) -> f64 {
+        let line = logs.lines().find(|line| line.contains(message)).unwrap();
+        let prefix = format!("{}=", field);
+        let value = line
+            .split_whitespace()
+            .find_map(|token| token.strip_prefix(prefix.as_str()))
+            .unwrap();
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_exit_logs_time_decay_dominated_attribution() {
+        // Nearer strikes decay faster, so the book collects theta on the shorts
+        let mut options_chain = create_test_options_chain();
+        for contract in options_chain
+            .calls
+            .values_mut()
+            .chain(options_chain.puts.values_mut())
+        {
+            contract.greeks.theta = -0.10 + (contract.strike - 400.0).abs() * 0.002;
+        }
+
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let mut generator = IronCondorSignalGenerator::new(IronCondorConfig {
+                profit_target: ProfitTarget::PctOfMax(10.0),
+                ..Default::default()
+            });
+            generator.generate_signal_with_options_chain(&options_chain);
+            // Unchanged price and IV a little over a day later: the time exit fires
+            options_chain.timestamp += chrono::Duration::hours(25);
+            let exit = generator.generate_signal_with_options_chain(&options_chain);
+            assert!(matches!(
+                exit,
+                Some(IronCondorSignal::Exit {
+                    reason: ExitReason::TimeExit,
+                    ..
+                })
+            ));
+        });
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let field = |name| log_field(&logs, "Exit P&L attribution", name);
+        assert_eq!(field("underlying_pnl"), 0.0);
+        assert_eq!(field("vol_pnl"), 0.0);
+        assert!(field("time_decay_pnl") > 0.0);
+        assert_eq!(field("total_pnl"), field("time_decay_pnl"));
+    }
+
+    #[test]
+    fn test_find_closest_strike_skips_crossed_market() {
+        let generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        let mut options_chain = create_test_options_chain();
+
+        // Cross the 405 call so the next closest valid strike must be chosen
+        let crossed = options_chain.get_call_mut(405.0).unwrap();
+        crossed.bid = crossed.ask + 0.50;
+
+        let closest_call = generator.find_closest_strike(&options_chain, 404.0, OptionType::Call);
+        assert_eq!(closest_call, Some(400.0));
+    }
+
+    #[test]
+    fn test_price_ema_lags_noisy_spot() {
+        let config = IronCondorConfig {
+            price_smoothing_alpha: Some(0.3),
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+        // The chain's own underlying price stays stale: the EMA follows the mark
+        let options_chain = create_test_options_chain();
+
+        let prices = [400.0, 410.0, 395.0, 415.0, 405.0];
+        let mut expected_ema: Option<f64> = None;
+
+        for price in prices {
+            generator.generate_signal_with_mark_price(&options_chain, price);
+
+            let expected = match expected_ema {
+                Some(ema) => 0.3 * price + 0.7 * ema,
+                None => price,
+            };
+            expected_ema = Some(expected);
+
+            let ema = generator.state.price_ema.unwrap();
+            assert!((ema - expected).abs() < 1e-9);
+        }
+
+        // After the jump to 415 the EMA trails well below spot, after the drop it stays above
+        let ema = generator.state.price_ema.unwrap();
+        assert!(ema < 405.0 && ema > 400.0);
+    }
+
+    #[test]
+    fn test_price_ema_disabled_by_default() {
+        let mut generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        generator.generate_signal_with_options_chain(&create_test_options_chain());
+        assert_eq!(generator.state.price_ema, None);
+    }
+
+    #[test]
+    fn test_find_closest_strike_prefers_open_interest_on_near_ties() {
+        let mut options_chain = create_test_options_chain();
+        options_chain.get_call_mut(400.0).unwrap().open_interest = 100;
+        options_chain.get_call_mut(405.0).unwrap().open_interest = 5000;
+
+        // 400 is strictly closer to 402, so it wins without a tolerance
+        let strict = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        assert_eq!(
+            strict.find_closest_strike(&options_chain, 402.0, OptionType::Call),
+            Some(400.0)
+        );
+
+        // Within a $2 tolerance the two are near-ties and the higher OI 405 wins
+        let biased = IronCondorSignalGenerator::new(IronCondorConfig {
+            oi_tie_tolerance: 2.0,
+            ..Default::default()
+        });
+        assert_eq!(
+            biased.find_closest_strike(&options_chain, 402.0, OptionType::Call),
+            Some(405.0)
+        );
+    }
+
+    #[test]
+    fn test_find_closest_strike_measures_listed_strikes() {
+        let generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+
+        // 402.5 is 1.1 from the target and 405 is 1.4, though a whole-dollar 402 would lose
+        let strikes = (0..=24).map(|i| 370.0 + 2.5 * i as f64);
+        let options_chain = strike_chain(400.0, test_clock().now(), 30, strikes);
+        assert_eq!(
+            generator.find_closest_strike(&options_chain, 403.6, OptionType::Call),
+            Some(402.5)
+        );
+
+        // $0.50 strikes are listed side by side instead of overwriting each other
+        let strikes = (0..=10).map(|i| 397.5 + 0.5 * i as f64);
+        let options_chain = strike_chain(400.0, test_clock().now(), 30, strikes);
+        assert_eq!(options_chain.calls.len(), 11);
+        assert_eq!(options_chain.strike_increment(), Some(0.5));
+        assert_eq!(
+            generator.find_closest_strike(&options_chain, 400.4, OptionType::Call),
+            Some(400.5)
+        );
+    }
+
+    #[test]
+    fn test_widths_snap_to_strike_increment() {
+        // $25-increment index chain: a fixed $10 width would land back on the short strike
+        let strikes = (300..=500).step_by(25).map(f64::from);
+        let mut options_chain = strike_chain(400.0, test_clock().now(), 30, strikes);
+        // Cheapen the wings so the condor is a credit
+        for contract in [
+            options_chain
+                .calls
+                .get_mut(&OptionsChain::strike_key(450.0))
+                .unwrap(),
+            options_chain
+                .puts
+                .get_mut(&OptionsChain::strike_key(350.0))
+                .unwrap(),
+        ] {
+            contract.bid = 0.05;
+            contract.ask = 0.10;
+        }
+
+        let generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        let position = generator
+            .create_iron_condor_position(&options_chain)
+            .unwrap();
+
+        assert_eq!(position.short_call.strike, 425.0);
+        assert_eq!(position.long_call.strike, 450.0);
+        assert_eq!(position.short_put.strike, 375.0);
+        assert_eq!(position.long_put.strike, 350.0);
+        for width in [
+            position.long_call.strike - position.short_call.strike,
+            position.short_put.strike - position.long_put.strike,
+        ] {
+            assert_eq!(width % 25.0, 0.0);
+            assert!(width > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_trailing_stop_fires_before_profit_target() {
+        let config = IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(0.80),
+            trailing_stop_activation_pct: 0.50,
+            trailing_stop_pct: Some(0.25),
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+        let mut options_chain = create_test_options_chain();
+
+        let position = match generator.generate_signal_with_options_chain(&options_chain) {
+            Some(IronCondorSignal::Enter { position, .. }) => position,
+            _ => panic!("Expected entry signal"),
+        };
+        let short_call = position.short_call.strike;
+        let premium = position.entry_premium;
+
+        // Run up to 60% of max profit: arms the trailing stop but is short of the 80% target
+        options_chain.timestamp += chrono::Duration::hours(1);
+        let peak_mark = short_call + 0.40 * premium;
+        assert!(
+            generator
+                .generate_signal_with_mark_price(&options_chain, peak_mark)
+                .is_none()
+        );
+        let peak = generator.state.peak_profit_pct[&position.id];
+        assert!((peak - 60.0).abs() < 1e-9);
+
+        // Retrace to 40%: more than a quarter of the peak has been given back
+        options_chain.timestamp += chrono::Duration::hours(1);
+        let retrace_mark = short_call + 0.60 * premium;
+        match generator.generate_signal_with_mark_price(&options_chain, retrace_mark) {
+            Some(IronCondorSignal::Exit { reason, .. }) => {
+                assert_eq!(reason, ExitReason::ProfitTargetPartial)
+            }
+            other => panic!("Expected trailing stop exit, got {:?}", other),
+        }
+        assert!(generator.state.peak_profit_pct.is_empty());
+    }
+
+    #[test]
+    fn test_delta_stop_fires_on_short_put_breach() {
+        let config = IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(10.0), // Out of reach so only the delta stop can fire
+            stop_loss_pct: 10.0,
+            delta_stop: Some(0.30),
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+        let mut options_chain = create_test_options_chain();
+
+        let position = match generator.generate_signal_with_options_chain(&options_chain) {
+            Some(IronCondorSignal::Enter { position, .. }) => position,
+            _ => panic!("Expected entry signal"),
+        };
+        let short_put = position.short_put.strike;
+
+        // Modest drop: the short put is still well out of the money
+        options_chain.timestamp += chrono::Duration::hours(1);
+        options_chain.underlying_price = 392.0;
+        options_chain.get_put_mut(short_put).unwrap().greeks.delta = -0.22;
+        assert!(
+            generator
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+
+        // Further drop pushes the short put's delta past the threshold
+        options_chain.timestamp += chrono::Duration::hours(1);
+        options_chain.underlying_price = 385.0;
+        options_chain.get_put_mut(short_put).unwrap().greeks.delta = -0.34;
+        match generator.generate_signal_with_options_chain(&options_chain) {
+            Some(IronCondorSignal::Exit { reason, .. }) => {
+                assert_eq!(reason, ExitReason::DeltaStop)
+            }
+            other => panic!("Expected delta stop exit, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mid_mark_source_values_from_chain_quotes() {
+        let config = IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(0.50),
+            stop_loss_pct: 10.0,
+            mark_source: Some(MarkSource::Mid),
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+        let mut options_chain = create_test_options_chain();
+
+        let position = match generator.generate_signal_with_options_chain(&options_chain) {
+            Some(IronCondorSignal::Enter { position, .. }) => position,
+            _ => panic!("Expected entry signal"),
+        };
+
+        // Unchanged quotes: at mid the condor is only up the half-spread, short of the target
+        options_chain.timestamp += chrono::Duration::hours(1);
+        assert!(
+            generator
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+
+        // Short legs decay to nearly nothing: the mid mark now clears the profit target
+        options_chain.timestamp += chrono::Duration::hours(1);
+        for strike in [position.short_call.strike, position.long_call.strike] {
+            let call = options_chain.get_call_mut(strike).unwrap();
+            call.bid = 0.01;
+            call.ask = 0.03;
+        }
+        for strike in [position.short_put.strike, position.long_put.strike] {
+            let put = options_chain.get_put_mut(strike).unwrap();
+            put.bid = 0.01;
+            put.ask = 0.03;
+        }
+        match generator.generate_signal_with_options_chain(&options_chain) {
+            Some(IronCondorSignal::Exit { reason, .. }) => {
+                assert_eq!(reason, ExitReason::ProfitTarget)
+            }
+            other => panic!("Expected profit target exit, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_zero_body_width_builds_iron_fly() {
+        let generator = IronCondorSignalGenerator::new(IronCondorConfig {
+            body_width: 0.0,
+            ..Default::default()
+        });
+        let mut options_chain = create_test_options_chain();
+        options_chain.underlying_price = 401.0;
+
+        let position = generator
+            .create_iron_condor_position(&options_chain)
+            .unwrap();
+
+        // Both shorts snap to the ATM strike, wings keep their usual width
+        assert_eq!(position.short_call.strike, 400.0);
+        assert_eq!(position.short_put.strike, 400.0);
+        assert_eq!(position.long_call.strike, 410.0);
+        assert_eq!(position.long_put.strike, 390.0);
+    }
+
+    /// Enter, then mark once at `low_pnl` and once at `high_pnl` (per-share P&L) and
+    /// return whether each mark produced a profit target exit
+    fn profit_target_hits(
+        profit_target: ProfitTarget,
+        low_pnl: f64,
+        high_pnl: f64,
+    ) -> (bool, bool) {
+        let mut generator = IronCondorSignalGenerator::new(IronCondorConfig {
+            profit_target,
+            stop_loss_pct: 10.0,
+            ..Default::default()
+        });
+        let mut options_chain = create_test_options_chain();
+
+        let position = match generator.generate_signal_with_options_chain(&options_chain) {
+            Some(IronCondorSignal::Enter { position, .. }) => position,
+            _ => panic!("Expected entry signal"),
+        };
+        // Inside the call spread the intrinsic P&L is the credit minus the intrusion
+        let mark_for_pnl = |pnl: f64| position.short_call.strike + position.entry_premium - pnl;
+
+        let mut hit = |pnl: f64| {
+            options_chain.timestamp += chrono::Duration::hours(1);
+            matches!(
+                generator.generate_signal_with_mark_price(&options_chain, mark_for_pnl(pnl)),
+                Some(IronCondorSignal::Exit {
+                    reason: ExitReason::ProfitTarget,
+                    ..
+                })
+            )
+        };
+        (hit(low_pnl), hit(high_pnl))
+    }
+
+    #[test]
+    fn test_dollar_profit_target() {
+        // $50 per contract: $40 of profit holds, $60 closes
+        assert_eq!(
+            profit_target_hits(ProfitTarget::Dollars(50.0), 0.40, 0.60),
+            (false, true)
+        );
+    }
+
+    #[test]
+    fn test_pct_of_max_profit_target() {
+        let premium = {
+            let mut generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+            match generator.generate_signal_with_options_chain(&create_test_options_chain()) {
+                Some(IronCondorSignal::Enter { position, .. }) => position.entry_premium,
+                _ => panic!("Expected entry signal"),
+            }
+        };
+
+        // 50% of max: 40% of the credit holds, 60% closes
+        assert_eq!(
+            profit_target_hits(ProfitTarget::PctOfMax(0.50), 0.40 * premium, 0.60 * premium),
+            (false, true)
+        );
+    }
+
+    #[test]
+    fn test_strike_search_window_limits_candidates() {
+        let mut options_chain = create_test_options_chain();
+        let template = options_chain.get_call(400.0).unwrap().clone();
+        for strike in (200..=600).step_by(5) {
+            let contract = OptionsContract {
+                strike: strike as f64,
+                ..template.clone()
+            };
+            options_chain
+                .calls
+                .entry(OptionsChain::strike_key(strike as f64))
+                .or_insert(contract);
+        }
+
+        // Without a window the absurdly far strike is reachable
+        let unbounded = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        assert_eq!(
+            unbounded.find_closest_strike(&options_chain, 550.0, OptionType::Call),
+            Some(550.0)
+        );
+
+        // Four strikes either side of the 400 spot caps the search at 420
+        let windowed = IronCondorSignalGenerator::new(IronCondorConfig {
+            strike_search_window: Some(4),
+            ..Default::default()
+        });
+        assert_eq!(
+            windowed.find_closest_strike(&options_chain, 550.0, OptionType::Call),
+            Some(420.0)
+        );
+        assert_eq!(
+            windowed.find_closest_strike(&options_chain, 250.0, OptionType::Call),
+            Some(380.0)
+        );
+    }
+
+    #[test]
+    fn test_entry_skipped_when_net_vega_limit_exceeded() {
+        let mut options_chain = create_test_options_chain();
+        // Vega peaks at the money: shorts at 420/380 carry 0.20, wings at 430/370 0.15,
+        // so the condor is net short 0.10 vega
+        for contract in options_chain
+            .calls
+            .values_mut()
+            .chain(options_chain.puts.values_mut())
+        {
+            contract.greeks.vega = 0.30 - 0.005 * (contract.strike - 400.0).abs();
+        }
+
+        let mut limited = IronCondorSignalGenerator::new(IronCondorConfig {
+            max_net_vega: Some(0.05),
+            ..Default::default()
+        });
+        assert!(
+            limited
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+        assert!(limited.state.active_positions.is_empty());
+
+        let mut roomy = IronCondorSignalGenerator::new(IronCondorConfig {
+            max_net_vega: Some(0.20),
+            ..Default::default()
+        });
+        match roomy.generate_signal_with_options_chain(&options_chain) {
+            Some(IronCondorSignal::Enter { position, .. }) => {
+                assert!((position.net_greeks().vega + 0.10).abs() < 1e-9);
+            }
+            other => panic!("Expected entry signal, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_iv_rank_window_caps_history_and_warms_up() {
+        let mut generator = IronCondorSignalGenerator::new(IronCondorConfig {
+            iv_rank_window: 3,
+            ..Default::default()
+        });
+        let mut options_chain = create_test_options_chain();
+        let mut observe = |generator: &mut IronCondorSignalGenerator, iv: f64| {
+            for contract in options_chain
+                .calls
+                .values_mut()
+                .chain(options_chain.puts.values_mut())
+            {
+                contract.implied_volatility = iv;
+            }
+            options_chain.timestamp += chrono::Duration::hours(1);
+            generator.generate_signal_with_options_chain(&options_chain);
+        };
+
+        // Warming up: too few samples to rank against
+        observe(&mut generator, 0.10);
+        observe(&mut generator, 0.30);
+        assert_eq!(generator.state.iv_history.len(), 2);
+        assert_eq!(generator.iv_rank(), None);
+
+        observe(&mut generator, 0.20);
+        assert!((generator.iv_rank().unwrap() - 0.5).abs() < 1e-9);
+
+        // The oldest samples (10% and 30%) drop out of the window
+        observe(&mut generator, 0.25);
+        observe(&mut generator, 0.40);
+        assert_eq!(
+            generator.state.iv_history,
+            VecDeque::from([0.20, 0.25, 0.40])
+        );
+        assert_eq!(generator.iv_rank(), Some(1.0));
+    }
+
+    #[test]
+    fn test_iv_rank_scales_profit_target() {
+        // Enter after an IV history spanning 10-30%, then mark at 50% of max profit
+        let enter_and_mark = |entry_iv: f64| {
+            let mut generator = IronCondorSignalGenerator::new(IronCondorConfig {
+                profit_target: ProfitTarget::PctOfMax(0.50),
+                stop_loss_pct: 10.0,
+                iv_rank_profit_scaling: true,
+                iv_rank_window: 3,
+                ..Default::default()
+            });
+            generator.state.iv_history = VecDeque::from([0.10, 0.30]);
+
+            let mut options_chain = create_test_options_chain();
+            for contract in options_chain
+                .calls
+                .values_mut()
+                .chain(options_chain.puts.values_mut())
+            {
+                contract.implied_volatility = entry_iv;
+            }
+            let position = match generator.generate_signal_with_options_chain(&options_chain) {
+                Some(IronCondorSignal::Enter { position, .. }) => position,
+                _ => panic!("Expected entry signal"),
+            };
+
+            options_chain.timestamp += chrono::Duration::hours(1);
+            let half_profit_mark = position.short_call.strike + position.entry_premium * 0.50;
+            let signal =
+                generator.generate_signal_with_mark_price(&options_chain, half_profit_mark);
+            (position.entry_iv_rank.unwrap(), signal)
+        };
+
+        // Rank 0.1 lowers the target to 30% of max, so 50% closes the position
+        let (low_rank, low_signal) = enter_and_mark(0.12);
+        assert!((low_rank - 0.1).abs() < 1e-9);
+        assert!(matches!(
+            low_signal,
+            Some(IronCondorSignal::Exit {
+                reason: ExitReason::ProfitTarget,
+                ..
+            })
+        ));
+
+        // Rank 0.9 raises it to 70% of max, so the position is held
+        let (high_rank, high_signal) = enter_and_mark(0.28);
+        assert!((high_rank - 0.9).abs() < 1e-9);
+        assert!(high_signal.is_none());
+    }
+
+    #[test]
+    fn test_one_sided_chain_returns_none_without_panicking() {
+        let generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        let mut options_chain = create_test_options_chain();
+        options_chain.puts.clear();
+
+        assert!(
+            generator
+                .create_iron_condor_position(&options_chain)
+                .is_none()
+        );
+        assert_eq!(
+            describe_strikes(&options_chain.puts),
+            "no strikes available"
+        );
+        assert_eq!(
+            describe_strikes(&options_chain.calls),
+            "13 (min: 370, max: 430)"
+        );
+    }
+
+    #[test]
+    fn test_chain_for_other_symbol_is_ignored() {
+        let mut generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+        let mut options_chain = create_test_options_chain();
+        options_chain.underlying = "QQQ".to_string();
+
+        assert!(
+            generator
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+        assert!(generator.state.active_positions.is_empty());
+        assert!(generator.state.iv_history.is_empty());
+
+        options_chain.underlying = "spy".to_string();
+        assert!(matches!(
+            generator.generate_signal_with_options_chain(&options_chain),
+            Some(IronCondorSignal::Enter { .. })
+        ));
+    }
+
+    #[test]
+    fn test_min_short_extrinsic_rejects_nearly_intrinsic_short() {
+        // Strikes are picked around a lagging 400 reference while spot has dropped to 379,
+        // leaving the 380 short put in the money and quoted at almost pure intrinsic
+        let mut options_chain = create_test_options_chain();
+        options_chain.underlying_price = 379.0;
+        let short_put = options_chain.get_put_mut(380.0).unwrap();
+        short_put.bid = 1.02;
+        short_put.ask = 1.06;
+
+        let generator = |min_short_extrinsic| {
+            let mut generator = IronCondorSignalGenerator::new(IronCondorConfig {
+                price_smoothing_alpha: Some(0.0),
+                min_short_extrinsic,
+                ..Default::default()
+            });
+            generator.state.price_ema = Some(400.0);
+            generator
+        };
+
+        let position = generator(None)
+            .create_iron_condor_position(&options_chain)
+            .unwrap();
+        assert_eq!(position.short_put.strike, 380.0);
+
+        assert!(
+            generator(Some(0.25))
+                .create_iron_condor_position(&options_chain)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_targets_round_to_configured_strike_increment() {
+        assert_eq!(round_to_increment(411.3, 2.5), 412.5);
+        assert_eq!(round_to_increment(401.2, 2.5), 400.0);
+        assert_eq!(round_to_increment(0.74, 0.5), 0.5);
+
+        // $2.50-increment product with one stray 411 strike in the data, which throws
+        // the detected increment off to 1.0 and lands the short call on it
+        let strikes = (0..=24).map(|i| 370.0 + 2.5 * i as f64).chain([411.0]);
+        let options_chain = strike_chain(400.0, test_clock().now(), 30, strikes);
+        assert_eq!(options_chain.strike_increment(), Some(1.0));
+
+        // Short call target 400 * (1 + 0.0565 / 2) = 411.3
+        let position = |strike_increment| {
+            IronCondorSignalGenerator::new(IronCondorConfig {
+                body_width: 0.0565,
+                strike_increment,
+                ..Default::default()
+            })
+            .create_iron_condor_position(&options_chain)
+            .unwrap()
+        };
+        assert_eq!(position(None).short_call.strike, 411.0);
+        let position = position(Some(2.5));
+        assert_eq!(position.short_call.strike, 412.5);
+        assert_eq!(position.short_put.strike, 387.5);
+    }
+
+    #[test]
+    fn test_blackout_window_suppresses_entries() {
+        let base_time = test_clock().now();
+        let config = IronCondorConfig {
+            blackout_windows: vec![(
+                base_time - chrono::Duration::hours(12),
+                base_time + chrono::Duration::hours(12),
+            )],
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+
+        let mut options_chain = create_test_options_chain();
+        options_chain.timestamp = base_time;
+        assert!(
+            generator
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+        assert!(generator.state.active_positions.is_empty());
+
+        // Entries resume once the window has passed
+        options_chain.timestamp = base_time + chrono::Duration::days(1);
+        let signal = generator.generate_signal_with_options_chain(&options_chain);
+        assert!(matches!(signal, Some(IronCondorSignal::Enter { .. })));
+    }
+
+    #[test]
+    fn test_max_otm_pct_rejects_deep_tail_delta_strikes() {
+        // Deltas fall off slowly enough that 0.16 delta sits 80 points (20%) from spot
+        let strikes = (300..=500).step_by(5).map(f64::from);
+        let mut options_chain = strike_chain(400.0, test_clock().now(), 30, strikes);
+        let contracts = options_chain.calls.values_mut();
+        for contract in contracts.chain(options_chain.puts.values_mut()) {
+            let distance = (contract.strike - 400.0).abs();
+            let magnitude = (0.5 - distance * 0.0042).max(0.01);
+            contract.greeks.delta = match contract.option_type {
+                OptionType::Call => magnitude,
+                OptionType::Put => -magnitude,
+            };
+            contract.bid = (5.0 - distance * 0.05).max(0.05);
+            contract.ask = contract.bid + 0.05;
+        }
+
+        let position = |max_otm_pct| {
+            IronCondorSignalGenerator::new(IronCondorConfig {
+                strike_selection: StrikeSelection::Delta,
+                max_otm_pct,
+                ..Default::default()
+            })
+            .create_iron_condor_position(&options_chain)
+        };
+
+        let uncapped = position(None).unwrap();
+        assert_eq!(uncapped.short_call.strike, 480.0);
+        assert_eq!(uncapped.short_put.strike, 320.0);
+        assert!(position(Some(0.10)).is_none());
+    }
+
+    #[test]
+    fn test_delta_search_respects_strike_search_window() {
+        // Deltas fall off slowly enough that 0.16 delta sits 80 points from spot
+        let strikes = (300..=500).step_by(5).map(f64::from);
+        let mut options_chain = strike_chain(400.0, test_clock().now(), 30, strikes);
+        let contracts = options_chain.calls.values_mut();
+        for contract in contracts.chain(options_chain.puts.values_mut()) {
+            let distance = (contract.strike - 400.0).abs();
+            let magnitude = (0.5 - distance * 0.0042).max(0.01);
+            contract.greeks.delta = match contract.option_type {
+                OptionType::Call => magnitude,
+                OptionType::Put => -magnitude,
+            };
+        }
+
+        let generator = |strike_search_window| {
+            IronCondorSignalGenerator::new(IronCondorConfig {
+                strike_selection: StrikeSelection::Delta,
+                strike_search_window,
+                ..Default::default()
+            })
+        };
+        assert_eq!(
+            generator(None).find_strike_by_delta(&options_chain, OptionType::Call),
+            Some(480.0)
+        );
+
+        // Four strikes either side of the 400 spot keep the shorts within 380..=420
+        let windowed = generator(Some(4));
+        assert_eq!(
+            windowed.find_strike_by_delta(&options_chain, OptionType::Call),
+            Some(420.0)
+        );
+        assert_eq!(
+            windowed.find_strike_by_delta(&options_chain, OptionType::Put),
+            Some(380.0)
+        );
+    }
+
+    #[test]
+    fn test_entries_only_fire_on_configured_weekdays() {
+        // A week of daily chains starting on a Monday
+        let monday = DateTime::parse_from_rfc3339("2025-06-02T15:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(monday.weekday(), Weekday::Mon);
+
+        let entry_days: Vec<Weekday> = (0..7)
+            .map(|day| monday + chrono::Duration::days(day))
+            .filter(|timestamp| {
+                let mut generator = IronCondorSignalGenerator::new(IronCondorConfig {
+                    entry_weekdays: vec![Weekday::Mon],
+                    ..Default::default()
+                });
+                let mut options_chain = create_test_options_chain();
+                options_chain.timestamp = *timestamp;
+                matches!(
+                    generator.generate_signal_with_options_chain(&options_chain),
+                    Some(IronCondorSignal::Enter { .. })
+                )
+            })
+            .map(|timestamp| timestamp.weekday())
+            .collect();
+
+        assert_eq!(entry_days, vec![Weekday::Mon]);
+    }
+
+    #[test]
+    fn test_delta_targeting_falls_back_to_percentage_without_greeks() {
+        let delta_config = IronCondorConfig {
+            strike_selection: StrikeSelection::Delta,
+            ..Default::default()
+        };
+
+        // The test chain carries no Greeks, so delta targeting picks the percentage strikes
+        let options_chain = create_test_options_chain();
+        assert!(!options_chain.has_greeks());
+        let percentage = IronCondorSignalGenerator::new(IronCondorConfig::default())
+            .create_iron_condor_position(&options_chain)
+            .unwrap();
+        let fallback = IronCondorSignalGenerator::new(delta_config.clone())
+            .create_iron_condor_position(&options_chain)
+            .unwrap();
+        assert_eq!(fallback.short_call.strike, percentage.short_call.strike);
+        assert_eq!(fallback.short_put.strike, percentage.short_put.strike);
+
+        // With Greeks present the shorts land on the strikes nearest 0.16 delta
+        let mut options_chain = create_test_options_chain();
+        for contract in options_chain
+            .calls
+            .values_mut()
+            .chain(options_chain.puts.values_mut())
+        {
+            let magnitude = (0.5 - (contract.strike - 400.0).abs() * 0.02).max(0.01);
+            contract.greeks.delta = match contract.option_type {
+                OptionType::Call => magnitude,
+                OptionType::Put => -magnitude,
+            };
+        }
+        let position = IronCondorSignalGenerator::new(delta_config)
+            .create_iron_condor_position(&options_chain)
+            .unwrap();
+        assert_eq!(position.short_call.strike, 415.0);
+        assert_eq!(position.short_put.strike, 385.0);
+    }
+
+    #[test]
+    fn test_partial_fill_is_topped_up_on_next_event() {
+        // Every leg trades 100 contracts, so 2% participation fills 2 of the 3 wanted
+        let mut generator = IronCondorSignalGenerator::new(IronCondorConfig {
+            quantity: 3,
+            max_volume_participation: Some(0.02),
+            profit_target: ProfitTarget::PctOfMax(10.0),
+            ..Default::default()
+        });
+        let mut options_chain = create_test_options_chain();
+
+        let Some(IronCondorSignal::Enter { position, .. }) =
+            generator.generate_signal_with_options_chain(&options_chain)
+        else {
+            panic!("expected an entry");
+        };
+        assert_eq!(position.quantity, 2);
+        let premium_per_contract = position.entry_premium / 2.0;
+
+        options_chain.timestamp += chrono::Duration::hours(1);
+        let Some(IronCondorSignal::Adjust {
+            position_id,
+            added_quantity,
+            added_premium,
+            fills,
+            ..
+        }) = generator.generate_signal_with_options_chain(&options_chain)
+        else {
+            panic!("expected a top-up");
+        };
+        assert_eq!(position_id, position.id);
+        assert_eq!(added_quantity, 1);
+        assert!((added_premium - premium_per_contract).abs() < 1e-9);
+        assert_eq!(fills.len(), 4);
+
+        // Same quotes both times, so the blended premium is 3x the per-contract credit
+        let topped_up = &generator.state.active_positions[&position.id];
+        assert_eq!(topped_up.quantity, 3);
+        assert!((topped_up.entry_premium - 3.0 * premium_per_contract).abs() < 1e-9);
+
+        // Nothing left to add
+        options_chain.timestamp += chrono::Duration::hours(1);
+        assert!(
+            generator
+                .generate_signal_with_options_chain(&options_chain)
+                .is_none()
+        );
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_find_closest_strike_is_deterministic_closest(
+            // Whole and half dollars so exact ties between $5 strikes come up often
+            half_dollars in 700u32..=880,
+            is_call in proptest::bool::ANY,
+        ) {
+            let target = half_dollars as f64 / 2.0;
+            let option_type = if is_call { OptionType::Call } else { OptionType::Put };
+            let generator = IronCondorSignalGenerator::new(IronCondorConfig::default());
+
+            let strikes = (370..=430).step_by(5).map(|strike| strike as f64);
+            let closest = strikes
+                .map(|strike| (strike, (strike - target).abs()))
+                .reduce(|best, candidate| {
+                    let further_otm = match option_type {
+                        OptionType::Call => candidate.0 > best.0,
+                        OptionType::Put => candidate.0 < best.0,
+                    };
+                    if candidate.1 < best.1 || (candidate.1 == best.1 && further_otm) {
+                        candidate
+                    } else {
+                        best
+                    }
+                })
+                .map(|(strike, _)| strike);
+
+            // Freshly built chains get new hash seeds, so iteration order varies
+            for _ in 0..3 {
+                let options_chain = create_test_options_chain();
+                let selected = generator.find_closest_strike(&options_chain, target, option_type);
+                proptest::prop_assert_eq!(selected, closest);
+            }
+        }
+    }
 }
@@ -1 +1,2 @@
 pub mod iron_condor;
+pub mod signal_recorder;
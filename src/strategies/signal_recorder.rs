@@ -0,0 +1,87 @@
+use crate::models::options_data::OptionsChain;
+use crate::strategies::iron_condor::{IronCondorSignal, IronCondorSignalGenerator};
+
+/// In-memory sink for signals so strategies can be tested without the backtest runner
+#[derive(Debug, Clone, Default)]
+#[allow(dead_code)]
+pub struct SignalRecorder {
+    signals: Vec<IronCondorSignal>,
+}
+
+#[allow(dead_code)]
+impl SignalRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run every chain through the generator in order and record all emitted signals
+    pub fn run(generator: &mut IronCondorSignalGenerator, chains: &[OptionsChain]) -> Self {
+        let mut recorder = Self::new();
+        for chain in chains {
+            if let Some(signal) = generator.generate_signal_with_options_chain(chain) {
+                recorder.record(signal);
+            }
+        }
+        recorder
+    }
+
+    /// Push a signal into the sink
+    pub fn record(&mut self, signal: IronCondorSignal) {
+        self.signals.push(signal);
+    }
+
+    /// All recorded signals in emission order
+    pub fn signals(&self) -> &[IronCondorSignal] {
+        &self.signals
+    }
+
+    pub fn len(&self) -> usize {
+        self.signals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signals.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::iron_condor::{ExitReason, IronCondorConfig, ProfitTarget};
+    use crate::test_fixtures::create_test_chain;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_records_enter_then_exit_sequence() {
+        let config = IronCondorConfig {
+            profit_target: ProfitTarget::PctOfMax(10.0), // Out of reach so the time exit closes the position
+            ..Default::default()
+        };
+        let mut generator = IronCondorSignalGenerator::new(config);
+
+        let start = Utc::now();
+        let chains: Vec<OptionsChain> = [0, 1, 2, 3]
+            .into_iter()
+            .map(|step| create_test_chain(400.0, start + Duration::hours(step * 12)))
+            .collect();
+
+        let recorder = SignalRecorder::run(&mut generator, &chains);
+
+        assert_eq!(recorder.len(), 2);
+        let entered_id = match &recorder.signals()[0] {
+            IronCondorSignal::Enter { position, .. } => position.id,
+            other => panic!("Expected entry first, got {:?}", other),
+        };
+        match &recorder.signals()[1] {
+            IronCondorSignal::Exit {
+                position_id,
+                reason,
+                ..
+            } => {
+                assert_eq!(*position_id, entered_id);
+                assert_eq!(*reason, ExitReason::TimeExit);
+            }
+            other => panic!("Expected exit second, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::backtest::runner::EnhancedMarketEvent;
+use crate::models::options_data::{
+    OptionType, OptionsChain, OptionsContract, OptionsContractParams,
+};
+
+/// SPY chain priced by `OptionsContract::new` at `timestamp`, with a call and a put at
+/// each of `strikes` expiring `days_to_expiry` days later
+pub fn strike_chain(
+    underlying_price: f64,
+    timestamp: DateTime<Utc>,
+    days_to_expiry: i64,
+    strikes: impl IntoIterator<Item = f64>,
+) -> OptionsChain {
+    let expiration = timestamp + Duration::days(days_to_expiry);
+    let mut calls = HashMap::new();
+    let mut puts = HashMap::new();
+
+    for strike in strikes {
+        for option_type in [OptionType::Call, OptionType::Put] {
+            let contract = OptionsContract::new(OptionsContractParams {
+                underlying: "SPY".to_string(),
+                option_type,
+                strike,
+                expiration,
+                underlying_price,
+                implied_vol: 0.20,
+                risk_free_rate: 0.05,
+                current_time: timestamp,
+            });
+            match option_type {
+                OptionType::Call => calls.insert(OptionsChain::strike_key(strike), contract),
+                OptionType::Put => puts.insert(OptionsChain::strike_key(strike), contract),
+            };
+        }
+    }
+
+    OptionsChain {
+        underlying: "SPY".to_string(),
+        expiration,
+        underlying_price,
+        underlying_price_estimated: false,
+        calls,
+        puts,
+        timestamp,
+    }
+}
+
+/// 30-day chain with $5 strikes from 370 to 430
+pub fn create_test_chain(underlying_price: f64, timestamp: DateTime<Utc>) -> OptionsChain {
+    let strikes = (370..=430).step_by(5).map(f64::from);
+    strike_chain(underlying_price, timestamp, 30, strikes)
+}
+
+/// Event with a quoted underlying price carrying the `create_test_chain` chain
+pub fn create_test_event(underlying_price: f64, timestamp: DateTime<Utc>) -> EnhancedMarketEvent {
+    let chain = create_test_chain(underlying_price, timestamp);
+    EnhancedMarketEvent {
+        symbol: "SPY".to_string(),
+        underlying_price,
+        underlying_price_estimated: false,
+        volume: 0.0,
+        implied_volatility: 0.20,
+        options_chains: HashMap::from([(chain.expiration.format("%Y-%m-%d").to_string(), chain)]),
+        timestamp,
+    }
+}